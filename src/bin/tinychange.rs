@@ -1,3 +1,5 @@
+//! Command line interface of tinychange
+
 use clap::Parser;
 use tinychange::TinyChangeArgs;
 
@@ -0,0 +1,841 @@
+use crate::config::{Config, Grouping};
+use crate::tinychange::{normalize_message, TinyChange};
+use globset::GlobSet;
+use miette::bail;
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Builds a regex matching the header of a section whose title starts with
+/// the given text, ignoring case
+pub fn regex_for_section(section: &str) -> Regex {
+    regex_for_section_pattern(&regex::escape(section))
+        .expect("Escaped section title should be a valid regex")
+}
+
+/// Same as [regex_for_section], with the title matched by a regex pattern
+/// instead, such as `upcoming|next release`
+pub fn regex_for_section_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(&format!(r"^#+\s*\[?\s*(?:{})\s*]?[^\n]*$", pattern))
+        .case_insensitive(true)
+        .build()
+}
+
+/// Checks that the section pattern from the configuration is a valid regex
+pub fn check_section_pattern(pattern: &str) -> Result<(), regex::Error> {
+    regex_for_section_pattern(pattern).map(|_| ())
+}
+
+/// Builds a regex matching the header of the given version's section, with
+/// or without the `v` prefix (eg. `## [1.2.0] - 2025-01-01` or `## v1.2.0`)
+pub fn regex_for_version(version: &str) -> Regex {
+    let version = version.strip_prefix('v').unwrap_or(version);
+    RegexBuilder::new(&format!(
+        r"^#+\s*\[?\s*v?{}\s*(?:]|\s|$)[^\n]*$",
+        regex::escape(version)
+    ))
+    .case_insensitive(true)
+    .build()
+    .unwrap()
+}
+
+static TITLE_HEADER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^#(?:[^#]|$)").unwrap());
+
+static RELEASE_HEADER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^##(?:[^#]|$)").unwrap());
+
+static SUBSECTION_HEADER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^###\s*\[?\s*(?<title>[^\]\n]*?)\s*]?\s*$").unwrap());
+
+/// Titles of the subsections that credit people instead of listing changes
+const CREDIT_TITLES: [&str; 5] = [
+    "contributors",
+    "new contributors",
+    "thanks",
+    "thanks to",
+    "credits",
+];
+
+/// Lists the titles of the `###` subsections of the `##` release sections,
+/// such as the change categories, most frequently used first
+///
+/// The unreleased section is skipped, as its content is not released yet,
+/// and so are the subsections crediting the contributors
+pub fn subsection_titles(content: &str) -> Vec<String> {
+    let lines = content.lines().map(Cow::Borrowed).collect::<Vec<_>>();
+    let unreleased = regex_for_section("unreleased");
+
+    let mut headers = vec![];
+    let mut search_in = 0..lines.len();
+    while let Some(release) = find_section(&lines, search_in, false, &RELEASE_HEADER) {
+        search_in = release.end..lines.len();
+        if unreleased.is_match(&lines[release.start]) {
+            continue;
+        }
+
+        let mut subsections_in = (release.start + 1)..release.end;
+        while let Some(subsection) = find_section(&lines, subsections_in, false, &SUBSECTION_HEADER)
+        {
+            headers.push(subsection.start);
+            subsections_in = subsection.end..release.end;
+        }
+    }
+
+    let mut titles: Vec<(String, usize)> = vec![];
+    for captures in headers
+        .into_iter()
+        .filter_map(|idx| SUBSECTION_HEADER.captures(&lines[idx]))
+    {
+        let title = &captures["title"];
+        let is_credit = CREDIT_TITLES
+            .iter()
+            .any(|credit| credit.eq_ignore_ascii_case(title.trim_end_matches(':')));
+        if title.is_empty() || is_credit {
+            continue;
+        }
+        match titles
+            .iter_mut()
+            .find(|(existing, _)| existing.eq_ignore_ascii_case(title))
+        {
+            Some((_, count)) => *count += 1,
+            None => titles.push((title.to_owned(), 1)),
+        }
+    }
+
+    // stable sort keeps the order of appearance for equally frequent titles
+    titles.sort_by(|(_, a), (_, b)| b.cmp(a));
+    titles.into_iter().map(|(title, _)| title).collect()
+}
+
+/// Finds the section in the changelog file that starts with the given regex
+/// and ends with the next section.
+///
+/// Returns the range of lines that the section spans
+pub fn find_section(
+    lines: &[Cow<str>],
+    search_in: Range<usize>,
+    ignore_level_matching: bool,
+    section_start: &Regex,
+) -> Option<Range<usize>> {
+    let mut start = None::<(usize, i32)>;
+    for (idx, line) in lines[search_in.clone()]
+        .iter()
+        .enumerate()
+        .filter(|l| l.1.starts_with('#'))
+    {
+        let idx = idx + search_in.start;
+        let level = line.chars().take_while(|c| *c == '#').count() as i32;
+        if let Some((start_idx, start_level)) = &start {
+            if level <= *start_level || ignore_level_matching {
+                return Some(*start_idx..idx);
+            }
+        } else if section_start.is_match(line) {
+            start = Some((idx, level));
+        }
+    }
+    if let Some((start_idx, _)) = start {
+        Some(start_idx..search_in.end)
+    } else {
+        None
+    }
+}
+
+/// Returns the body of the first section matching the given regex, without
+/// its header and surrounding blank lines
+pub fn section_body(content: &str, section_start: &Regex) -> Option<String> {
+    let lines = content.lines().map(Cow::Borrowed).collect::<Vec<_>>();
+    let section = find_section(&lines, 0..lines.len(), false, section_start)?;
+
+    let body = lines[(section.start + 1)..section.end].join("\n");
+    Some(body.trim_matches('\n').trim_end().to_owned())
+}
+
+/// Category subsections found inside a section of the changelog
+#[derive(Debug, Clone)]
+pub struct CategorySections {
+    /// Line ranges of the category subsections, in the order of the categories
+    pub sections: Vec<Option<Range<usize>>>,
+    /// First line of the earliest category subsection
+    pub start: usize,
+    /// End of the latest category subsection
+    pub end: usize,
+}
+
+impl CategorySections {
+    /// Finds the subsections of all categories inside the given section
+    ///
+    /// Fails if anything other than blank lines is found between the
+    /// category subsections, since it would be lost when the subsections
+    /// get rewritten
+    pub fn find(
+        lines: &[Cow<str>],
+        section: Range<usize>,
+        categories: &[String],
+    ) -> miette::Result<Self> {
+        let sections = categories
+            .iter()
+            .map(|category| {
+                find_section(lines, section.clone(), false, &regex_for_section(category))
+            })
+            .collect::<Vec<_>>();
+
+        let start = sections
+            .iter()
+            .flatten()
+            .map(|r| r.start)
+            .min()
+            .unwrap_or(section.end);
+        let end = sections
+            .iter()
+            .flatten()
+            .map(|r| r.end)
+            .max()
+            .unwrap_or(section.end);
+
+        #[allow(clippy::needless_range_loop)]
+        for idx in start..end {
+            if lines[idx].trim().is_empty() {
+                continue;
+            }
+
+            if !sections.iter().flatten().any(|range| range.contains(&idx)) {
+                bail!(
+                    "Unexpected content or unknown category in unreleased section at line {}: {}",
+                    idx + 1,
+                    lines[idx]
+                );
+            }
+        }
+
+        Ok(Self {
+            sections,
+            start,
+            end,
+        })
+    }
+
+    /// Returns the start of the region occupied by the category subsections,
+    /// including the blank lines preceding them
+    pub fn cutoff_start(&self, lines: &[Cow<str>]) -> usize {
+        let mut cutoff_start = self.start;
+        while cutoff_start > 0 && lines[cutoff_start - 1].trim().is_empty() {
+            cutoff_start -= 1;
+        }
+        cutoff_start
+    }
+}
+
+/// A single bullet point entry in the changelog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChangelogEntry {
+    pub message: String,
+    pub author: Option<String>,
+}
+
+/// Splits the `(by author)` suffix off the line, returning the rest of the
+/// line and the author
+///
+/// Not using a regex, since links to the profiles have parentheses
+fn split_inline_author(line: &str) -> Option<(&str, &str)> {
+    let (rest, author) = line.trim_end().strip_suffix(')')?.rsplit_once("(by ")?;
+    Some((rest.trim_end(), author.trim()))
+}
+
+/// Parses the bullet point entries in the given lines of the changelog
+///
+/// Authors are extracted from both the `(by author)` suffix and the
+/// `By: author` line that tinychange uses for multi-line entries
+pub fn parse_entries(lines: &[Cow<str>], first_line: usize) -> miette::Result<Vec<ChangelogEntry>> {
+    let mut entries: Vec<Vec<&str>> = vec![];
+
+    for (idx, line) in lines.iter().enumerate() {
+        if let Some(first) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| line.strip_prefix(marker))
+        {
+            entries.push(vec![first.trim_start()]);
+        } else if let Some(entry) = entries.last_mut() {
+            if line.trim().is_empty() {
+                entry.push("");
+            } else if let Some(line) = line.strip_prefix("  ") {
+                entry.push(line);
+            } else {
+                bail!(
+                    "Unexpected content in the middle of a list at line {}: {}",
+                    first_line + idx + 1,
+                    line
+                );
+            }
+        } else if !line.trim().is_empty() {
+            bail!(
+                "Unexpected content before the first list item at line {}: {}",
+                first_line + idx + 1,
+                line
+            );
+        }
+    }
+
+    Ok(entries
+        .into_iter()
+        .map(|mut lines| {
+            while lines.last().is_some_and(|line| line.trim().is_empty()) {
+                lines.pop();
+            }
+
+            let author = lines
+                .last()
+                .filter(|_| lines.len() > 1)
+                .and_then(|line| line.trim().strip_prefix("By:"))
+                .map(|author| author.trim().to_owned());
+            if author.is_some() {
+                lines.pop();
+            }
+
+            let message = normalize_message(&lines.join("\n"));
+            if author.is_some() {
+                return ChangelogEntry { message, author };
+            }
+
+            match split_inline_author(&message) {
+                Some((rest, author)) => ChangelogEntry {
+                    message: rest.to_owned(),
+                    author: Some(author.to_owned()),
+                },
+                None => ChangelogEntry {
+                    message,
+                    author: None,
+                },
+            }
+        })
+        .collect())
+}
+
+/// Merges the changes into the unreleased section of the changelog, returning
+/// the new content of the changelog
+///
+/// The unreleased section is created under the top-level changelog section
+/// if there is none. Doesn't touch the filesystem, so the caller is
+/// responsible for reading and writing the changelog file
+pub fn merge_changelog(
+    existing_changelog: &str,
+    changes: &[TinyChange],
+    config: &Config,
+) -> miette::Result<String> {
+    merge_changelog_with(existing_changelog, changes, config, |_| {})
+}
+
+/// Same as [merge_changelog], describing the steps to `report`
+pub fn merge_changelog_with(
+    existing_changelog: &str,
+    changes: &[TinyChange],
+    config: &Config,
+    mut report: impl FnMut(&str),
+) -> miette::Result<String> {
+    let unreleased_header = config.unreleased_section()?;
+    let changelog_header = config.changelog_section()?;
+    let hidden_authors = config.hidden_author_globs()?;
+    report(&format!("Merging {} changesets", changes.len()));
+
+    let mut lines = existing_changelog
+        .lines()
+        .map(Cow::Borrowed)
+        .collect::<Vec<_>>();
+
+    if let Some(unreleased_section) = find_section(
+        &lines,
+        0..lines.len(),
+        false,
+        &unreleased_header,
+    ) {
+        report("Found unreleased section, merging changes into it");
+        if config.grouping != Grouping::Category {
+            let body = lines[(unreleased_section.start + 1)..unreleased_section.end].join("\n");
+            let earlier = lines[..unreleased_section.start]
+                .iter()
+                .chain(&lines[unreleased_section.end..]);
+            let content = format_grouped(changes, config, &hidden_authors, Some(&body), earlier)?;
+
+            // keep the header, the rest of the section is rendered again
+            lines = lines[..=unreleased_section.start]
+                .iter()
+                .cloned()
+                .chain([Cow::Owned(content)])
+                .chain(lines[unreleased_section.end..].iter().cloned())
+                .collect();
+            return Ok(lines.join("\n"));
+        }
+
+        let existing =
+            CategorySections::find(&lines, unreleased_section.clone(), &config.section_titles())?;
+
+        let existing_sections = existing
+            .sections
+            .iter()
+            .map(|section| {
+                // skip the first line, which is the section header
+                section
+                    .as_ref()
+                    .map(|section| lines[(section.start + 1)..section.end].join("\n"))
+            })
+            .collect();
+
+        let earlier = lines[..unreleased_section.start]
+            .iter()
+            .chain(&lines[unreleased_section.end..]);
+        let content = format_changes(
+            changes,
+            config,
+            &hidden_authors,
+            Some(existing_sections),
+            earlier,
+        );
+
+        // discard the full unreleased section except for the header
+        let before = &lines[..existing.cutoff_start(&lines)];
+        let after = &lines[existing.end..];
+
+        lines = before
+            .iter()
+            .cloned()
+            .chain([Cow::Owned(content)])
+            .chain(after.iter().cloned())
+            .collect();
+    } else if let Some(place) = find_section(
+        &lines,
+        0..lines.len(),
+        true,
+        &changelog_header,
+    )
+    .map(|changelog_section| {
+        report("No unreleased section found, creating a new one under the changelog section");
+        changelog_section.end
+    })
+    .or_else(|| {
+        let first = lines.iter().position(|line| line.starts_with('#'))?;
+        if TITLE_HEADER.is_match(&lines[first]) {
+            // a title that doesn't match the pattern is still kept on top,
+            // along with the text under it
+            let title = find_section(&lines, first..lines.len(), true, &TITLE_HEADER)?;
+            report("No unreleased or changelog section found, creating an unreleased section under the title");
+            Some(title.end)
+        } else {
+            // changelogs without a title get the section in front of the first release
+            report("No unreleased or changelog section found, creating an unreleased section before the first release");
+            Some(first)
+        }
+    }) {
+        let content = if config.grouping == Grouping::Category {
+            format_changes(changes, config, &hidden_authors, None, lines.iter())
+        } else {
+            format_grouped(changes, config, &hidden_authors, None, lines.iter())?
+        };
+
+        lines.insert(place, Cow::Owned(content));
+        if place == 0 {
+            lines.insert(place, config.unreleased_header().into());
+        } else {
+            lines.insert(place, format!("\n{}", config.unreleased_header()).into());
+        }
+        if place > 0 && lines[place - 1].trim().is_empty() {
+            lines.remove(place - 1);
+        }
+    } else {
+        bail!("No unreleased or changelog section found in changelog file")
+    };
+
+    Ok(lines.join("\n"))
+}
+
+/// Renders the category subsections with the changes, appending them to the
+/// existing content of the subsections
+///
+/// The `earlier` lines are the rest of the changelog, used to tell the new
+/// contributors apart
+fn format_changes<'a>(
+    all_changes: &[TinyChange],
+    config: &Config,
+    hidden_authors: &GlobSet,
+    existing_sections: Option<Vec<Option<String>>>,
+    earlier: impl Iterator<Item = &'a Cow<'a, str>>,
+) -> String {
+    let mut builder = String::new();
+
+    for (idx, category) in config.categories.iter().enumerate() {
+        let existing = existing_sections
+            .as_ref()
+            .and_then(|sections| sections[idx].as_ref());
+
+        let changes = all_changes
+            .iter()
+            .filter(|change| &change.kind == category)
+            .collect::<Vec<_>>();
+
+        if changes.is_empty() && existing.is_none() {
+            continue;
+        }
+
+        // scoped changes go into the subsections of their scope
+        let entries = changes
+            .into_iter()
+            .map(|change| {
+                (
+                    change.scope.clone(),
+                    change
+                        .as_markdown_hiding(config, hidden_authors)
+                        .to_string(),
+                )
+            })
+            .collect();
+
+        builder.push_str(&format!("\n### {}\n", category));
+        let existing = existing.map(|existing| Groups::parse(existing, 4));
+        render_groups(&mut builder, existing, 4, entries);
+    }
+
+    if let Some(title) = config.contributors_title() {
+        let existing = existing_sections
+            .as_ref()
+            .and_then(|sections| sections[config.categories.len()].as_deref());
+        builder.push_str(&format_contributors(
+            all_changes,
+            config,
+            hidden_authors,
+            title,
+            existing,
+            earlier,
+        ));
+    }
+
+    builder
+}
+
+/// Renders the unreleased section for groupings other than by category,
+/// appending the changes to the existing content of the section
+///
+/// Fails if the existing content is grouped by category, since the entries
+/// would end up mixed with the new groups
+fn format_grouped<'a>(
+    changes: &[TinyChange],
+    config: &Config,
+    hidden_authors: &GlobSet,
+    existing: Option<&str>,
+    earlier: impl Iterator<Item = &'a Cow<'a, str>>,
+) -> miette::Result<String> {
+    let mut existing = existing.map(|existing| Groups::parse(existing, 3));
+    let existing_contributors = config.contributors_title().and_then(|title| {
+        existing
+            .as_mut()
+            .and_then(|existing| existing.take(title))
+            .map(|lines| lines.join("\n"))
+    });
+
+    let mismatched = existing
+        .iter()
+        .flat_map(|existing| &existing.groups)
+        .find(|(title, _, _)| {
+            config.grouping == Grouping::Flat
+                || config
+                    .categories
+                    .iter()
+                    .any(|category| category.eq_ignore_ascii_case(title))
+        });
+    if let Some((_, header, _)) = mismatched {
+        bail!(
+            help = "Merge the pending entries with the previous `grouping` first, or regroup them by hand",
+            "The unreleased section is grouped differently than the configured `grouping`: found `{}`",
+            header
+        );
+    }
+
+    // changes without a scope or a credited author are listed before the groups
+    let entries = changes
+        .iter()
+        .map(|change| {
+            let group = match config.grouping {
+                Grouping::Category | Grouping::Flat => None,
+                Grouping::Scope => change.scope.clone(),
+                Grouping::Author => config
+                    .credit(&change.author, hidden_authors)
+                    .map(Cow::into_owned),
+            };
+            (
+                group,
+                change
+                    .as_markdown_hiding(config, hidden_authors)
+                    .to_string(),
+            )
+        })
+        .collect();
+
+    let mut builder = String::new();
+    render_groups(&mut builder, existing, 3, entries);
+
+    if let Some(title) = config.contributors_title() {
+        builder.push_str(&format_contributors(
+            changes,
+            config,
+            hidden_authors,
+            title,
+            existing_contributors.as_deref(),
+            earlier,
+        ));
+    }
+
+    Ok(builder)
+}
+
+/// Subsections of a changelog section, along with the content preceding them
+struct Groups<'a> {
+    /// Content before the first subsection, such as the description or the
+    /// entries that don't belong to any subsection
+    preamble: Vec<&'a str>,
+    /// Titles, header lines and content of the subsections
+    groups: Vec<(String, &'a str, Vec<&'a str>)>,
+}
+
+impl<'a> Groups<'a> {
+    /// Splits the body of a section into the subsections with the given
+    /// header level. Deeper headers are kept in the content of the subsections
+    fn parse(body: &'a str, level: usize) -> Self {
+        let hashes = "#".repeat(level);
+        let mut groups = Self {
+            preamble: vec![],
+            groups: vec![],
+        };
+        for line in body.lines() {
+            let title = line
+                .strip_prefix(&hashes)
+                .filter(|rest| !rest.starts_with('#'))
+                .map(str::trim);
+            match (title, groups.groups.last_mut()) {
+                (Some(title), _) => {
+                    let title = title
+                        .strip_prefix('[')
+                        .and_then(|title| title.strip_suffix(']'))
+                        .unwrap_or(title);
+                    groups.groups.push((title.trim().to_owned(), line, vec![]));
+                }
+                (None, Some((_, _, content))) => content.push(line),
+                (None, None) => groups.preamble.push(line),
+            }
+        }
+        groups
+    }
+
+    /// Removes the subsection with the given title, returning its content
+    fn take(&mut self, title: &str) -> Option<Vec<&'a str>> {
+        let idx = self
+            .groups
+            .iter()
+            .position(|(existing, _, _)| existing.eq_ignore_ascii_case(title))?;
+        Some(self.groups.remove(idx).2)
+    }
+}
+
+/// Renders the subsections of the given header level, appending the entries
+/// to the existing subsections with the same title, and creating the missing
+/// ones in the order of appearance. Entries without a group are listed
+/// before the subsections
+fn render_groups(
+    builder: &mut String,
+    existing: Option<Groups>,
+    level: usize,
+    entries: Vec<(Option<String>, String)>,
+) {
+    let (preamble, mut groups) = match existing {
+        Some(existing) => (
+            Some(existing.preamble.join("\n")),
+            existing
+                .groups
+                .into_iter()
+                .map(|(title, header, content)| {
+                    (title, Cow::Borrowed(header), content.join("\n"), vec![])
+                })
+                .collect(),
+        ),
+        None => (None, vec![]),
+    };
+
+    let mut ungrouped = vec![];
+    for (group, entry) in entries {
+        let Some(group) = group else {
+            ungrouped.push(entry);
+            continue;
+        };
+        match groups
+            .iter_mut()
+            .find(|(title, _, _, _)| title.eq_ignore_ascii_case(&group))
+        {
+            Some((_, _, _, entries)) => entries.push(entry),
+            None => {
+                let header = format!("{} {}", "#".repeat(level), group);
+                groups.push((group, Cow::Owned(header), String::new(), vec![entry]));
+            }
+        }
+    }
+
+    let preamble = preamble.filter(|preamble| !preamble.trim().is_empty());
+    if preamble.is_some() || !ungrouped.is_empty() {
+        builder.push('\n');
+    }
+    if let Some(preamble) = preamble {
+        let preamble = preamble.trim();
+        builder.push_str(preamble);
+        builder.push('\n');
+        // the entries continue a list, but not the description of the section
+        let ends_with_list = preamble.lines().last().is_some_and(is_list_line);
+        if !ungrouped.is_empty() && !ends_with_list {
+            builder.push('\n');
+        }
+    }
+    for entry in ungrouped {
+        builder.push_str(&entry);
+        builder.push('\n');
+    }
+
+    for (_, header, content, entries) in groups {
+        builder.push_str(&format!("\n{}\n\n", header));
+        if !content.trim().is_empty() {
+            builder.push_str(content.trim());
+            builder.push('\n');
+        }
+        for entry in entries {
+            builder.push_str(&entry);
+            builder.push('\n');
+        }
+    }
+}
+
+/// Whether the line is a list item or the continuation of one
+fn is_list_line(line: &str) -> bool {
+    let item = ["-", "*", "+"].iter().any(|marker| {
+        line.strip_prefix(marker)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(' '))
+    });
+    item || line.starts_with("  ")
+}
+
+/// Whether any list item in the changelog content has the message as its text, with
+/// or without the category, scope and author around it
+pub fn has_entry(content: &str, message: &str) -> bool {
+    let message = message.trim();
+    content.lines().any(|line| {
+        let Some(item) = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|marker| line.trim_start().strip_prefix(marker))
+        else {
+            return false;
+        };
+        item.match_indices(message).any(|(idx, _)| {
+            let before = &item[..idx];
+            let after = &item[idx + message.len()..];
+            (before.is_empty() || before.ends_with("] ") || before.ends_with(": "))
+                && (after.is_empty() || after.starts_with(" ("))
+        })
+    })
+}
+
+/// Note added after the contributors who are not credited in the earlier releases
+const NEW_CONTRIBUTOR_NOTE: &str = "(first contribution)";
+
+/// Renders the contributors subsection, adding the authors of the changes
+/// that the existing list doesn't have yet
+fn format_contributors<'a>(
+    changes: &[TinyChange],
+    config: &Config,
+    hidden_authors: &GlobSet,
+    title: &str,
+    existing: Option<&str>,
+    earlier: impl Iterator<Item = &'a Cow<'a, str>>,
+) -> String {
+    let listed = |list: &str| {
+        list.lines()
+            .filter_map(|line| line.strip_prefix("- "))
+            .map(|item| {
+                item.trim()
+                    .trim_end_matches(NEW_CONTRIBUTOR_NOTE)
+                    .trim_end()
+                    .to_owned()
+            })
+            .collect::<Vec<_>>()
+    };
+    let existing_contributors = existing.map(listed).unwrap_or_default();
+
+    let mut contributors: Vec<(Cow<str>, &str)> = vec![];
+    for change in changes {
+        if config.is_hidden_author(&change.author, hidden_authors) {
+            continue;
+        }
+        let Some(credit) = config.credit(&change.author, hidden_authors) else {
+            continue;
+        };
+        let is_listed = |other: &str| other.eq_ignore_ascii_case(&credit);
+        if existing_contributors.iter().any(|c| is_listed(c))
+            || contributors.iter().any(|(c, _)| is_listed(c))
+        {
+            continue;
+        }
+        contributors.push((credit, &change.author));
+    }
+    contributors.sort_by_key(|(credit, _)| credit.to_lowercase());
+
+    if contributors.is_empty() && existing.is_none() {
+        return String::new();
+    }
+
+    let credited = if config.flag_new_contributors {
+        credited_authors(earlier, title)
+    } else {
+        vec![]
+    };
+
+    let mut builder = format!("\n### {}\n\n", title);
+    if let Some(existing) = existing {
+        builder.push_str(existing.trim());
+        builder.push('\n');
+    }
+    for (credit, author) in contributors {
+        builder.push_str(&format!("- {}", credit));
+        let is_new = !credited
+            .iter()
+            .any(|other| other.eq_ignore_ascii_case(&credit) || other.eq_ignore_ascii_case(author));
+        if config.flag_new_contributors && is_new {
+            builder.push(' ');
+            builder.push_str(NEW_CONTRIBUTOR_NOTE);
+        }
+        builder.push('\n');
+    }
+
+    builder
+}
+
+/// Collects the authors credited in the given lines, both after the entries
+/// and in the contributors subsections
+fn credited_authors<'a>(
+    lines: impl Iterator<Item = &'a Cow<'a, str>>,
+    contributors_title: &str,
+) -> Vec<String> {
+    let contributors_header = regex_for_section(contributors_title);
+    let mut in_contributors = false;
+    let mut authors = vec![];
+    for line in lines {
+        if line.starts_with('#') {
+            in_contributors = contributors_header.is_match(line);
+            continue;
+        }
+
+        let author = if let Some(item) = line.strip_prefix("- ").filter(|_| in_contributors) {
+            Some(
+                item.trim_end_matches(NEW_CONTRIBUTOR_NOTE)
+                    .trim()
+                    .to_owned(),
+            )
+        } else if let Some(author) = line.trim().strip_prefix("By:") {
+            Some(author.trim().to_owned())
+        } else {
+            split_inline_author(line).map(|(_, author)| author.to_owned())
+        };
+        authors.extend(author);
+    }
+    authors
+}
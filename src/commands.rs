@@ -1,3 +1,7 @@
+pub mod from_commits;
+pub mod import;
 pub mod init;
 pub mod merge;
 pub mod new;
+pub mod notes;
+pub mod schema;
@@ -0,0 +1,159 @@
+use crate::changelog::{has_entry, section_body};
+use crate::config::CommandOpts;
+use crate::git;
+use crate::tinychange::TinyChange;
+use clap::Args;
+use miette::IntoDiagnostic;
+use regex::Regex;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone, Args)]
+pub struct FromCommitsArgs {
+    /// Git revision range to read commits from (eg. `v1.0.0..HEAD`)
+    range: String,
+}
+
+static CONVENTIONAL_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?<type>[A-Za-z]+)(?:\([^)]*\))?(?<breaking>!)?:\s*(?<description>\S.*)$")
+        .unwrap()
+});
+
+/// A commit message that follows the Conventional Commits specification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConventionalCommit {
+    pub kind: String,
+    pub breaking: bool,
+    pub description: String,
+}
+
+impl ConventionalCommit {
+    /// Parses the commit message, returning `None` if the header does not
+    /// follow the Conventional Commits format
+    pub fn parse(message: &str) -> Option<Self> {
+        let mut lines = message.lines();
+        let captures = CONVENTIONAL_HEADER.captures(lines.next()?.trim())?;
+
+        let breaking = captures.name("breaking").is_some()
+            || lines.any(|line| {
+                line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+            });
+
+        Some(Self {
+            kind: captures["type"].to_lowercase(),
+            breaking,
+            description: captures["description"].trim().to_owned(),
+        })
+    }
+
+    /// Returns the message for the changelog entry, with the first letter capitalized
+    pub fn message(&self) -> String {
+        let mut chars = self.description.chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+}
+
+impl FromCommitsArgs {
+    pub fn run(self, opts: CommandOpts) -> miette::Result<()> {
+        let created = from_commits(&opts, &self.range)?;
+        opts.info(&format!("Created {} tinychanges", created));
+
+        Ok(())
+    }
+}
+
+/// Creates tinychanges from the conventional commits in the git revision
+/// range, skipping the commits that already have a tinychange or an entry
+/// in the unreleased section of the changelog
+///
+/// Returns the number of created tinychanges
+pub fn from_commits(opts: &CommandOpts, range: &str) -> miette::Result<usize> {
+    let commits = git::log(opts.workdir(), range)?;
+
+    let tinychanges_dir = std::path::absolute(opts.tinychanges_dir()).into_diagnostic()?;
+    let with_tinychanges: HashSet<String> =
+        git::commits_touching(opts.workdir(), range, &tinychanges_dir)?
+            .into_iter()
+            .collect();
+
+    // the tinychanges of earlier runs may have been merged already, while
+    // the released entries may share the message with a newer commit
+    let unreleased = if opts.changelog_file().exists() {
+        let content = fs_err::read_to_string(opts.changelog_file()).into_diagnostic()?;
+        section_body(&content, &opts.config().unreleased_section()?).unwrap_or_default()
+    } else {
+        String::new()
+    };
+
+    let mut created = 0;
+    for commit in commits {
+        let short_hash = &commit.hash[..commit.hash.len().min(7)];
+
+        if with_tinychanges.contains(&commit.hash) {
+            opts.info(&format!(
+                "Skipping commit {}: it already has a tinychange",
+                short_hash
+            ));
+            continue;
+        }
+
+        let Some(parsed) = ConventionalCommit::parse(&commit.message) else {
+            opts.info(&format!(
+                "Skipping commit {}: not a conventional commit",
+                short_hash
+            ));
+            continue;
+        };
+
+        let category = parsed
+            .breaking
+            .then(|| opts.map_category("breaking"))
+            .flatten()
+            .or_else(|| opts.map_category(&parsed.kind));
+
+        let Some(category) = category else {
+            opts.warn(&format!(
+                "Skipping commit {}: no category for type `{}`",
+                short_hash, parsed.kind
+            ));
+            continue;
+        };
+
+        let change = TinyChange {
+            kind: category.to_owned(),
+            message: parsed.message(),
+            author: opts
+                .config()
+                .find_author(&commit.author_email)
+                .or_else(|| opts.config().find_author(&commit.author))
+                .map_or(commit.author, |(key, _)| key.to_owned()),
+            scope: None,
+        };
+
+        if has_entry(&unreleased, &change.message) {
+            opts.info(&format!(
+                "Skipping commit {}: the unreleased section already has it",
+                short_hash
+            ));
+            continue;
+        }
+
+        // salting with the commit hash gives the same filename on every run
+        let salt =
+            u64::from_str_radix(&commit.hash[..commit.hash.len().min(16)], 16).into_diagnostic()?;
+        if change.write_salted(opts, salt)?.is_none() {
+            opts.info(&format!(
+                "Skipping commit {}: tinychange already exists",
+                short_hash
+            ));
+            continue;
+        }
+
+        created += 1;
+    }
+
+    Ok(created)
+}
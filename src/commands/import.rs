@@ -0,0 +1,228 @@
+use crate::changelog::{find_section, parse_entries, CategorySections};
+use crate::commands::new::resolve_author;
+use crate::config::CommandOpts;
+use crate::fragments;
+use crate::tinychange::TinyChange;
+use clap::{Args, ValueEnum};
+use miette::{bail, miette, Context, IntoDiagnostic};
+use std::borrow::Cow;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Clone, Args)]
+pub struct ImportArgs {
+    /// Where to import the changes from
+    #[arg(long, value_enum, default_value_t)]
+    from: ImportSource,
+    /// Directory with the fragment files (defaults to the tool's default directory)
+    #[arg(long, value_name = "PATH")]
+    dir: Option<PathBuf>,
+    /// Do not delete fragment files after importing them
+    #[arg(long)]
+    keep: bool,
+    /// The author for entries that don't credit anyone (defaults to the git author if not provided)
+    #[arg(short, long)]
+    author: Option<String>,
+}
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, ValueEnum)]
+enum ImportSource {
+    /// Entries in the unreleased section of the changelog
+    #[default]
+    Changelog,
+    /// `.changeset/*.md` files from changesets
+    Changesets,
+    /// `newsfragments/<issue>.<type>.md` files from towncrier
+    Towncrier,
+    /// `.changes/unreleased/*.yaml` files from changie
+    Changie,
+}
+
+impl ImportSource {
+    fn default_dir(&self) -> &'static str {
+        match self {
+            ImportSource::Changelog => unreachable!("Changelog is not a fragment source"),
+            ImportSource::Changesets => ".changeset",
+            ImportSource::Towncrier => "newsfragments",
+            ImportSource::Changie => ".changes/unreleased",
+        }
+    }
+}
+
+impl ImportArgs {
+    pub fn run(self, opts: CommandOpts) -> miette::Result<()> {
+        if self.from == ImportSource::Changelog {
+            self.import_changelog(opts)
+        } else {
+            self.import_fragments(opts)
+        }
+    }
+
+    fn import_fragments(self, opts: CommandOpts) -> miette::Result<()> {
+        let dir = self
+            .dir
+            .unwrap_or_else(|| opts.config_dir().join(self.from.default_dir()));
+
+        if !dir.exists() {
+            bail!("Fragment directory {} does not exist", dir.display());
+        }
+
+        let fragments = match self.from {
+            ImportSource::Changelog => unreachable!("Handled in `run`"),
+            ImportSource::Changesets => fragments::read_changesets(&dir)?,
+            ImportSource::Towncrier => fragments::read_towncrier(&dir)?,
+            ImportSource::Changie => fragments::read_changie(&dir)?,
+        };
+
+        if fragments.is_empty() {
+            opts.info("No fragments found, nothing to import");
+            return Ok(());
+        }
+
+        // map all the types first, so nothing gets imported if any of them is unknown
+        let kinds = fragments
+            .iter()
+            .map(|fragment| {
+                opts.map_category(&fragment.kind)
+                    .map(str::to_owned)
+                    .ok_or_else(|| {
+                        miette!(
+                            help = "Map it to one of the categories in the `category_map` configuration table",
+                            "Unknown change type `{}` in {}",
+                            fragment.kind,
+                            fragment.path.display()
+                        )
+                    })
+            })
+            .collect::<miette::Result<Vec<_>>>()?;
+
+        let mut fallback_author = self.author;
+        for (index, (fragment, kind)) in fragments.iter().zip(kinds).enumerate() {
+            let author = match &fragment.author {
+                Some(author) => author.clone(),
+                None => resolve_fallback_author(&opts, &mut fallback_author)?,
+            };
+
+            TinyChange {
+                kind,
+                message: fragment.message.clone(),
+                author,
+                scope: None,
+            }
+            .write_prefixed(&opts, &order_prefix(index, fragments.len()))?;
+        }
+
+        if !self.keep {
+            for fragment in &fragments {
+                fs_err::remove_file(&fragment.path).into_diagnostic()?;
+            }
+        }
+
+        opts.info(&format!(
+            "Imported {} fragments from {}",
+            fragments.len(),
+            dir.display()
+        ));
+
+        Ok(())
+    }
+
+    fn import_changelog(self, opts: CommandOpts) -> miette::Result<()> {
+        if !opts.changelog_file().exists() {
+            opts.info("No changelog file found, nothing to import");
+            return Ok(());
+        }
+
+        let old_content = fs_err::read_to_string(opts.changelog_file()).into_diagnostic()?;
+        let lines = old_content.lines().map(Cow::Borrowed).collect::<Vec<_>>();
+
+        let Some(unreleased_section) = find_section(
+            &lines,
+            0..lines.len(),
+            false,
+            &opts.config().unreleased_section()?,
+        ) else {
+            opts.info("No unreleased section found, nothing to import");
+            return Ok(());
+        };
+
+        let existing =
+            CategorySections::find(&lines, unreleased_section, &opts.config().section_titles())?;
+
+        let mut entries = vec![];
+        for (category, section) in opts.categories().iter().zip(&existing.sections) {
+            let Some(section) = section else {
+                continue;
+            };
+
+            // skip the first line, which is the section header
+            let body = &lines[(section.start + 1)..section.end];
+            for entry in parse_entries(body, section.start + 1)
+                .with_context(|| format!("Failed to import the `{}` section", category))?
+            {
+                if entry.message.is_empty() {
+                    bail!("Empty entry in the `{}` section", category);
+                }
+                entries.push((category.clone(), entry));
+            }
+        }
+
+        if entries.is_empty() {
+            opts.info("No entries found in the unreleased section, nothing to import");
+            return Ok(());
+        }
+
+        let mut fallback_author = self.author;
+        for (index, (kind, entry)) in entries.iter().cloned().enumerate() {
+            let author = match entry.author {
+                Some(author) => author,
+                None => resolve_fallback_author(&opts, &mut fallback_author)?,
+            };
+
+            TinyChange {
+                kind,
+                message: entry.message,
+                author,
+                scope: None,
+            }
+            .write_prefixed(&opts, &order_prefix(index, entries.len()))?;
+        }
+
+        // leave the unreleased section empty, except for the header and description
+        let before = &lines[..existing.cutoff_start(&lines)];
+        let after = &lines[existing.end..];
+        let mut content = before.join("\n");
+        if !after.is_empty() {
+            content.push_str("\n\n");
+            content.push_str(&after.join("\n"));
+        }
+
+        fs_err::write(opts.changelog_file(), content)
+            .into_diagnostic()
+            .context("Failed to write changelog file")?;
+
+        opts.info(&format!(
+            "Imported {} entries from the unreleased section",
+            entries.len()
+        ));
+
+        Ok(())
+    }
+}
+
+/// Resolves the author for entries that don't credit anyone, remembering it
+/// so the user is asked at most once
+fn resolve_fallback_author(
+    opts: &CommandOpts,
+    fallback_author: &mut Option<String>,
+) -> miette::Result<String> {
+    let author = resolve_author(opts, fallback_author.take())?;
+    *fallback_author = Some(author.clone());
+    Ok(author)
+}
+
+/// Prefix for the filename of the imported entry at the given index, so that
+/// merging lists the entries in their original order
+fn order_prefix(index: usize, count: usize) -> String {
+    let width = count.to_string().len();
+    format!("{:0width$}-", index + 1)
+}
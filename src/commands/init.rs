@@ -1,18 +1,342 @@
+use crate::changelog::subsection_titles;
+use crate::report::{Level, Reporter};
 use miette::{bail, Context, IntoDiagnostic};
-use std::path::PathBuf;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 
-pub fn run(config_path: PathBuf, command_name: &str) -> miette::Result<()> {
+const DEFAULT_CONFIG: &str = include_str!("../tinychange.default.toml");
+const DEFAULT_TINYCHANGES_DIR: &str = ".tinychange";
+const DEFAULT_CHANGELOG: &str = "CHANGELOG.md";
+
+pub(crate) const NAMING_SCHEMES: [&str; 6] =
+    ["buzzword", "lorem", "hash", "slug", "date", "branch"];
+
+const TINYCHANGES_README: &str = "# Tinychanges
+
+This directory contains pending changelog entries, one per file, created with
+[tinychange](https://github.com/juh9870/tinychange).
+
+- Run `tinychange` to add an entry for your change
+- Run `tinychange merge` to move all entries into the changelog
+";
+
+const CHANGELOG_SKELETON: &str = "# Changelog
+
+All notable changes to this project will be documented in this file.
+
+## [Unreleased]
+";
+
+/// Set of categories to start the configuration with
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub(crate) enum Preset {
+    #[default]
+    KeepAChangelog,
+    ConventionalCommits,
+    Minimal,
+    /// Categories already used in the existing changelog
+    Existing(Vec<String>),
+}
+
+impl Preset {
+    pub(crate) const ALL: [Preset; 3] = [
+        Preset::KeepAChangelog,
+        Preset::ConventionalCommits,
+        Preset::Minimal,
+    ];
+
+    /// Picks the categories of the existing changelog, if it has any
+    fn infer(changelog: &Path) -> miette::Result<Option<Self>> {
+        if !changelog.exists() {
+            return Ok(None);
+        }
+        let content = fs_err::read_to_string(changelog)
+            .into_diagnostic()
+            .context("Failed to read changelog file")?;
+        let mut categories = subsection_titles(&content);
+        if categories.is_empty() {
+            return Ok(None);
+        }
+
+        // a changelog following keep-a-changelog gets the categories it
+        // hasn't used yet as well
+        let standard = Preset::KeepAChangelog.categories();
+        let is_standard = |category: &String| {
+            standard
+                .iter()
+                .any(|standard| standard.eq_ignore_ascii_case(category))
+        };
+        if categories.iter().all(is_standard) {
+            for category in standard {
+                if !categories.iter().any(|c| c.eq_ignore_ascii_case(category)) {
+                    categories.push(category.to_owned());
+                }
+            }
+        }
+
+        Ok(Some(Preset::Existing(categories)))
+    }
+
+    pub(crate) fn categories(&self) -> Vec<&str> {
+        let categories: &[&str] = match self {
+            Preset::Existing(categories) => return categories.iter().map(String::as_str).collect(),
+            Preset::KeepAChangelog => &[
+                "Added",
+                "Changed",
+                "Deprecated",
+                "Removed",
+                "Fixed",
+                "Security",
+            ],
+            Preset::ConventionalCommits => &[
+                "Breaking Changes",
+                "Features",
+                "Bug Fixes",
+                "Performance",
+                "Documentation",
+            ],
+            Preset::Minimal => &["Added", "Changed", "Fixed"],
+        };
+        categories.to_vec()
+    }
+
+    /// Mapping of conventional commit types, for categories not covered by
+    /// the default mapping
+    fn category_map(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Preset::KeepAChangelog | Preset::Minimal | Preset::Existing(_) => &[],
+            Preset::ConventionalCommits => &[
+                ("breaking", "Breaking Changes"),
+                ("major", "Breaking Changes"),
+                ("feat", "Features"),
+                ("minor", "Features"),
+                ("fix", "Bug Fixes"),
+                ("patch", "Bug Fixes"),
+                ("perf", "Performance"),
+                ("docs", "Documentation"),
+            ],
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Preset::KeepAChangelog => "keep-a-changelog",
+            Preset::ConventionalCommits => "conventional-commits",
+            Preset::Minimal => "minimal",
+            Preset::Existing(_) => "existing changelog",
+        };
+        write!(f, "{} ({})", name, self.categories().join(", "))
+    }
+}
+
+/// Answers to the init prompts
+#[derive(Debug, Clone)]
+pub(crate) struct Setup {
+    pub(crate) changelog: String,
+    pub(crate) tinychanges_dir: String,
+    pub(crate) naming: &'static str,
+    pub(crate) preset: Preset,
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Self {
+            changelog: DEFAULT_CHANGELOG.to_owned(),
+            tinychanges_dir: DEFAULT_TINYCHANGES_DIR.to_owned(),
+            naming: NAMING_SCHEMES[0],
+            preset: Preset::default(),
+        }
+    }
+}
+
+impl Setup {
+    /// Default answers, with the categories of the existing changelog if there is one
+    fn detect(config_dir: &Path) -> miette::Result<Self> {
+        let preset = Preset::infer(&config_dir.join(DEFAULT_CHANGELOG))?.unwrap_or_default();
+        Ok(Self {
+            preset,
+            ..Default::default()
+        })
+    }
+
+    fn prompt(config_dir: &Path) -> miette::Result<Self> {
+        let changelog = inquire::Text::new("Path to the changelog file:")
+            .with_default(DEFAULT_CHANGELOG)
+            .prompt()
+            .into_diagnostic()?;
+        let changelog = changelog.trim().to_owned();
+        let tinychanges_dir = inquire::Text::new("Directory to store tinychanges in:")
+            .with_default(DEFAULT_TINYCHANGES_DIR)
+            .prompt()
+            .into_diagnostic()?;
+        let naming = inquire::Select::new(
+            "Naming scheme for tinychange files:",
+            NAMING_SCHEMES.to_vec(),
+        )
+        .prompt()
+        .into_diagnostic()?;
+
+        // offer the categories of the existing changelog first
+        let mut presets = Preset::ALL.to_vec();
+        if let Some(existing) = Preset::infer(&config_dir.join(&changelog))? {
+            presets.insert(0, existing);
+        }
+        let preset = inquire::Select::new("Categories to use:", presets)
+            .prompt()
+            .into_diagnostic()?;
+
+        Ok(Self {
+            changelog,
+            tinychanges_dir: tinychanges_dir.trim().to_owned(),
+            naming,
+            preset,
+        })
+    }
+
+    /// Renders the configuration file, filling the answers into the default one
+    pub(crate) fn config(&self) -> String {
+        let mut config = DEFAULT_CONFIG.to_owned();
+        config = replace_line(
+            &config,
+            "tinylogs_dir = ",
+            &format!("tinylogs_dir = {}", toml_string(&self.tinychanges_dir)),
+        );
+        config = replace_line(
+            &config,
+            "changelog = ",
+            &format!("changelog = {}", toml_string(&self.changelog)),
+        );
+        let categories = self
+            .preset
+            .categories()
+            .iter()
+            .map(|category| toml_string(category))
+            .collect::<Vec<_>>()
+            .join(", ");
+        config = replace_line(
+            &config,
+            "categories = ",
+            &format!("categories = [{}]", categories),
+        );
+        if self.naming != NAMING_SCHEMES[0] {
+            config = replace_line(
+                &config,
+                "# naming = \"",
+                &format!("naming = {}", toml_string(self.naming)),
+            );
+        }
+
+        let category_map = self.preset.category_map();
+        if !category_map.is_empty() {
+            // replace the commented out example with the actual table
+            if let Some(idx) = config.find("# [category_map]") {
+                config.truncate(idx);
+            }
+            config.push_str("[category_map]\n");
+            for (kind, category) in category_map {
+                config.push_str(&format!("{} = {}\n", kind, toml_string(category)));
+            }
+        }
+
+        config
+    }
+}
+
+/// Replaces the first line starting with the prefix
+fn replace_line(text: &str, prefix: &str, replacement: &str) -> String {
+    let mut replaced = false;
+    text.lines()
+        .map(|line| {
+            if !replaced && line.starts_with(prefix) {
+                replaced = true;
+                replacement
+            } else {
+                line
+            }
+        })
+        .chain([""])
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_owned()).to_string()
+}
+
+pub fn run(
+    config_path: PathBuf,
+    command_name: &str,
+    interactive: bool,
+    reporter: &dyn Reporter,
+) -> miette::Result<()> {
     if config_path.exists() {
         bail!("Configuration file already exists");
     }
 
-    fs_err::write(&config_path, include_str!("../tinychange.default.toml"))
+    let config_dir = match config_path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    };
+
+    if interactive {
+        let setup = Setup::prompt(&config_dir)?;
+        fs_err::write(&config_path, setup.config())
+            .into_diagnostic()
+            .context("Failed to write config file")?;
+
+        create_tinychanges_dir(&config_dir.join(&setup.tinychanges_dir))?;
+
+        let changelog = config_dir.join(&setup.changelog);
+        if !changelog.exists() {
+            fs_err::write(&changelog, CHANGELOG_SKELETON)
+                .into_diagnostic()
+                .context("Failed to write changelog file")?;
+        }
+    } else {
+        // without the questions, only the configuration file is written, as
+        // init always did, with the categories of the existing changelog
+        let setup = Setup::detect(&config_dir)?;
+        let config = if setup.preset.categories() == Preset::KeepAChangelog.categories() {
+            DEFAULT_CONFIG.to_owned()
+        } else {
+            setup.config()
+        };
+        fs_err::write(&config_path, config)
+            .into_diagnostic()
+            .context("Failed to write config file")?;
+    }
+
+    reporter.report(
+        Level::Info,
+        "tinychange configuration initialized successfully! What's next?",
+    );
+    reporter.report(
+        Level::Info,
+        &format!("- Edit the configuration file at {}", config_path.display()),
+    );
+    reporter.report(
+        Level::Info,
+        &format!("- Run `{}` to start creating tinychanges", command_name),
+    );
+
+    Ok(())
+}
+
+/// Creates the tinychanges directory, with a `.gitkeep` so it's committed
+/// even when empty, and a README explaining what it's for
+fn create_tinychanges_dir(dir: &Path) -> miette::Result<()> {
+    fs_err::create_dir_all(dir)
         .into_diagnostic()
-        .context("Failed to write config file")?;
+        .context("Failed to create tinychanges directory")?;
 
-    println!("tinychange configuration initialized successfully! What's next?");
-    println!("- Edit the configuration file at {}", config_path.display());
-    println!("- Run `{}` to start creating tinychanges", command_name);
+    for (name, content) in [(".gitkeep", ""), ("README.md", TINYCHANGES_README)] {
+        let path = dir.join(name);
+        if !path.exists() {
+            fs_err::write(&path, content).into_diagnostic()?;
+        }
+    }
 
     Ok(())
 }
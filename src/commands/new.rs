@@ -1,36 +1,39 @@
 use crate::config::CommandOpts;
-use crate::tinychange::TinyChange;
+use crate::git::{self, run_cmd};
+use crate::tinychange::{normalize_message, TinyChange};
 use clap::Args;
 use miette::{bail, Context, IntoDiagnostic};
-use std::ffi::OsStr;
-use std::path::Path;
+use std::io::Read;
+use std::path::PathBuf;
 
 #[derive(Debug, Default, Clone, Args)]
 pub struct NewArgs {
     /// The kind of change (must be one of the categories from the configuration)
     #[arg(short, long)]
     kind: Option<String>,
-    /// The message describing the change
-    #[arg(short, long)]
+    /// The message describing the change (use `-` to read it from stdin)
+    #[arg(short, long, conflicts_with = "message_file")]
     message: Option<String>,
+    /// Read the message describing the change from a file
+    #[arg(long, value_name = "PATH")]
+    message_file: Option<PathBuf>,
     /// The author of the change (defaults to the git author if not provided)
     #[arg(short, long)]
     author: Option<String>,
+    /// Package or topic of the change, used as the subdirectory of the tinychanges directory to write the file into
+    #[arg(short, long)]
+    scope: Option<String>,
+    /// Name of the tinychange file, without the extension (generated if not provided)
+    #[arg(long, value_name = "STEM")]
+    name: Option<String>,
+    /// Overwrite the tinychange file if it already exists (requires `--name`)
+    #[arg(long, requires = "name")]
+    force: bool,
 }
 
 impl NewArgs {
     pub fn run(self, opts: CommandOpts) -> miette::Result<()> {
-        let author = if let Some(author) = self.author {
-            author
-        } else if let Some(name) = find_author(&opts)? {
-            name
-        } else if opts.interactive() {
-            inquire::Text::new("Who is the author of this change?")
-                .prompt()
-                .into_diagnostic()?
-        } else {
-            bail!("No author provided")
-        };
+        let author = resolve_author(&opts, self.author)?;
 
         let kind = if let Some(kind) = self.kind {
             if opts.categories().contains(&kind) {
@@ -43,10 +46,28 @@ impl NewArgs {
                 .prompt()
                 .into_diagnostic()?
         } else {
-            bail!("No change type provided")
+            bail!(
+                help = format!(
+                    "Pass one of the categories with `{} new --kind`",
+                    opts.command_name()
+                ),
+                "No change type provided"
+            )
         };
 
-        let message = if let Some(message) = self.message {
+        let message = if let Some(path) = self.message_file {
+            let message = fs_err::read_to_string(path)
+                .into_diagnostic()
+                .context("Failed to read message file")?;
+            normalize_message(&message)
+        } else if self.message.as_deref() == Some("-") {
+            let mut message = String::new();
+            std::io::stdin()
+                .read_to_string(&mut message)
+                .into_diagnostic()
+                .context("Failed to read message from stdin")?;
+            normalize_message(&message)
+        } else if let Some(message) = self.message {
             message
         } else if opts.interactive() {
             let prompt = if kind.ends_with("ed") {
@@ -54,82 +75,138 @@ impl NewArgs {
             } else {
                 "Describe the change".to_string()
             };
-            inquire::Text::new(&prompt).prompt().into_diagnostic()?
+            let message = if opts.editor() {
+                inquire::Editor::new(&prompt)
+                    .with_file_extension(".md")
+                    .prompt()
+                    .into_diagnostic()?
+            } else {
+                inquire::Text::new(&prompt).prompt().into_diagnostic()?
+            };
+            normalize_message(&message)
         } else {
-            bail!("No message provided")
+            bail!(
+                help = format!(
+                    "Pass the message with `{} new --message`, or use `--message -` to read it from stdin",
+                    opts.command_name()
+                ),
+                "No message provided"
+            )
         };
 
-        if message.is_empty() {
-            bail!("Empty message")
-        }
-
         let change = TinyChange {
             kind,
             message,
             author,
+            scope: self.scope,
         };
+        change.validate(&opts)?;
 
-        let name = change.filename(&opts)?;
-        let path = opts.tinychanges_dir().join(&name);
+        if let Some(name) = self.name {
+            let extension = format!(".{}", opts.extension());
+            let name = name.strip_suffix(&extension).unwrap_or(&name);
+            if name.is_empty() || sanitise_file_name::sanitise(name) != name {
+                bail!("Invalid tinychange file name: {}", name);
+            }
 
-        fs_err::create_dir_all(opts.tinychanges_dir())
-            .into_diagnostic()
-            .context("Failed to create tinychange directory")?;
-        fs_err::write(path, change.serialize())
-            .into_diagnostic()
-            .context("Failed to write tinychange file")?;
+            let filename = format!("{}{}", name, extension);
+            let Some(path) = change.write_named(&opts, &filename, self.force)? else {
+                bail!(
+                    help = "Use `--force` to overwrite it",
+                    "Tinychange file {} already exists",
+                    filename
+                );
+            };
+            opts.debug(&format!("Created tinychange at {}", path.display()));
+        } else {
+            let path = change.write(&opts)?;
+            opts.debug(&format!("Created tinychange at {}", path.display()));
+        }
 
         Ok(())
     }
 }
 
-fn find_author(opts: &CommandOpts) -> miette::Result<Option<String>> {
-    fn run_cmd<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
-        workdir: &Path,
-        cmd: &str,
-        args: I,
-    ) -> Option<String> {
-        let out = std::process::Command::new(cmd)
-            .args(args)
-            .current_dir(workdir)
-            .output()
-            .ok()?;
+/// Validates the tinychange and writes it into a new file with a generated
+/// name, returning the path to it
+pub fn create(opts: &CommandOpts, change: &TinyChange) -> miette::Result<PathBuf> {
+    change.validate(opts)?;
+    change.write(opts)
+}
 
-        if out.status.success() {
-            String::from_utf8(out.stdout)
-                .ok()
-                .map(|x| x.trim().to_owned())
-                .filter(|x| !x.is_empty())
-        } else {
-            None
+/// Returns the given author, falling back to the git author or prompting the user
+pub fn resolve_author(opts: &CommandOpts, author: Option<String>) -> miette::Result<String> {
+    let author = if let Some(author) = author {
+        author
+    } else if let Some((author, source)) = opts.author() {
+        // explicitly configured names are used as they are
+        opts.debug(&format!("Found author from {}: {}", source, author));
+        author.to_owned()
+    } else if let Some(name) = git_author(opts) {
+        canonical_author(opts, name)
+    } else if opts.interactive() {
+        inquire::Text::new("Who is the author of this change?")
+            .prompt()
+            .into_diagnostic()?
+    } else {
+        bail!(
+            help = format!(
+                "Pass the author with `{} new --author`",
+                opts.command_name()
+            ),
+            "No author provided"
+        )
+    };
+
+    Ok(author)
+}
+
+/// Resolves the git identity through the `.mailmap` and the `authors`
+/// configuration table, so the author is credited the same way on every machine
+fn canonical_author(opts: &CommandOpts, name: String) -> String {
+    let (name, email) = match git::author_email(opts.workdir()) {
+        Some(email) => git::check_mailmap(opts.workdir(), &name, &email)
+            .map_or((name, Some(email)), |(name, email)| (name, Some(email))),
+        None => (name, None),
+    };
+
+    let found = email
+        .and_then(|email| opts.config().find_author(&email))
+        .or_else(|| opts.config().find_author(&name));
+    match found {
+        Some((key, _)) => {
+            opts.debug(&format!("Mapped author {} to {}", name, key));
+            key.to_owned()
         }
+        None => name,
     }
+}
 
-    let name = if let Some(author) = run_cmd(opts.workdir(), "git", ["config", "author.name"]) {
-        opts.println(&format!(
+/// Finds the name of the author in the git configuration or environment
+fn git_author(opts: &CommandOpts) -> Option<String> {
+    if let Some(author) = run_cmd(opts.workdir(), "git", ["config", "author.name"]) {
+        opts.debug(&format!(
             "Found author from git author.name config: {}",
             author
         ));
         Some(author)
-    } else if let Some(author) = std::env::var("GIT_AUTHOR_NAME")
-        .ok()
+    } else if let Some(author) = opts
+        .env_var("GIT_AUTHOR_NAME")
         .map(|x| x.trim().to_owned())
         .filter(|x| !x.is_empty())
     {
-        opts.println(&format!(
+        opts.debug(&format!(
             "Found author from GIT_AUTHOR_NAME environment variable: {}",
             author
         ));
         Some(author)
     } else if let Some(author) = run_cmd(opts.workdir(), "git", ["config", "user.name"]) {
-        opts.println(&format!(
+        opts.debug(&format!(
             "Found author from git user.name config: {}",
             author
         ));
         Some(author)
     } else {
         None
-    };
-
-    Ok(name)
+    }
 }
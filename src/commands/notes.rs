@@ -0,0 +1,43 @@
+use crate::changelog::{regex_for_version, section_body};
+use crate::config::CommandOpts;
+use clap::Args;
+use miette::{bail, miette, Context, IntoDiagnostic};
+
+#[derive(Debug, Default, Clone, Args)]
+pub struct NotesArgs {
+    /// The version to print the release notes of (eg. `1.2.0`)
+    #[arg(
+        value_name = "VERSION",
+        required_unless_present = "unreleased",
+        conflicts_with = "unreleased"
+    )]
+    release: Option<String>,
+    /// Print the notes of the unreleased section instead
+    #[arg(short, long)]
+    unreleased: bool,
+}
+
+impl NotesArgs {
+    pub fn run(self, opts: CommandOpts) -> miette::Result<()> {
+        if !opts.changelog_file().exists() {
+            bail!("No changelog file found");
+        }
+
+        let content = fs_err::read_to_string(opts.changelog_file())
+            .into_diagnostic()
+            .context("Failed to read changelog file")?;
+
+        let (section, name) = match &self.release {
+            Some(version) if !self.unreleased => (regex_for_version(version), version.as_str()),
+            _ => (opts.config().unreleased_section()?, "unreleased"),
+        };
+
+        let notes = section_body(&content, &section)
+            .ok_or_else(|| miette!("No {} section found in changelog file", name))?;
+
+        // the notes are the output of this command, so they are printed even in silent mode
+        println!("{}", notes);
+
+        Ok(())
+    }
+}
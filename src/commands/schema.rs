@@ -0,0 +1,11 @@
+use crate::config::Config;
+use miette::IntoDiagnostic;
+
+pub fn run() -> miette::Result<()> {
+    let schema = schemars::schema_for!(Config);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema).into_diagnostic()?
+    );
+    Ok(())
+}
@@ -1,40 +1,613 @@
+use crate::changelog::regex_for_section_pattern;
 use crate::naming::NameType;
-use miette::bail;
+use crate::report::{Level, NullReporter, Reporter, SharedReporter};
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use miette::{bail, Context, IntoDiagnostic};
+use regex::Regex;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+mod validate;
+
+/// Project configuration, as stored in `tinychange.toml`
+///
+/// Use [Config::load] to read it, or [Config::new] to start from the defaults
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[non_exhaustive]
 pub struct Config {
+    /// Path to the directory where the tinychanges are stored (relative to this config file)
     pub tinylogs_dir: PathBuf,
+    /// Path to the CHANGELOG file (relative to this config file)
     pub changelog: PathBuf,
 
+    /// Allowed change categories
     pub categories: Vec<String>,
+    /// Filename naming scheme, defaults to `buzzword`
     #[serde(default)]
-    pub naming: NameType,
+    pub naming: Option<NameType>,
+    /// Maximum length of the filename, including the 8 symbols-long hash
+    /// suffix but excluding the extension. Defaults to 127
     #[serde(default)]
+    #[schemars(range(min = 8))]
     pub max_filename_length: Option<usize>,
+    /// Seed for generating filenames, making them reproducible
+    #[serde(default)]
+    pub naming_seed: Option<u64>,
+    /// Maps change types used by other tools (such as conventional commit
+    /// types) to categories
+    #[serde(default)]
+    pub category_map: BTreeMap<String, String>,
+    /// Extension of the tinychange files, defaults to `md`
+    #[serde(default)]
+    pub extension: Option<String>,
+    /// Glob patterns of files in the tinychanges directory that are not
+    /// tinychanges. `README.md` is always ignored
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    /// Treat subdirectories of the tinychanges directory as the scope (eg.
+    /// package) of the changes inside them, shown in the changelog
+    #[serde(default)]
+    pub directory_scopes: bool,
+    /// How authors are credited in the changelog, by their name in the
+    /// tinychange files
+    #[serde(default)]
+    pub authors: BTreeMap<String, AuthorInfo>,
+    /// Glob patterns of authors that are not credited in the changelog, such
+    /// as bots. Their entries are still listed, just without the attribution
+    #[serde(default)]
+    pub hidden_authors: Vec<String>,
+    /// Attribution shown instead of the hidden authors, eg. `automated`. The
+    /// attribution is left out if not set
+    #[serde(default)]
+    pub hidden_author_credit: Option<String>,
+    /// Where the authors are credited, after every entry by default
+    #[serde(default)]
+    pub attribution: Attribution,
+    /// Title of the contributors subsection, defaults to `Contributors`
+    #[serde(default)]
+    pub contributors_title: Option<String>,
+    /// Mark the contributors who are not credited in the earlier releases
+    /// of the changelog
+    #[serde(default)]
+    pub flag_new_contributors: bool,
+    /// How the entries are grouped in the unreleased section, by category
+    /// by default
+    #[serde(default)]
+    pub grouping: Grouping,
+    /// Regex matching the title of the unreleased section, `unreleased` by
+    /// default
+    #[serde(default)]
+    pub unreleased_pattern: Option<String>,
+    /// Header of the unreleased section created when the changelog has none,
+    /// `## [Unreleased]` by default
+    #[serde(default)]
+    pub unreleased_header: Option<String>,
+    /// Regex matching the title of the section the unreleased section is
+    /// created under, `changelog` by default
+    #[serde(default)]
+    pub changelog_pattern: Option<String>,
+}
+
+/// How the entries are grouped in the unreleased section of the changelog
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Grouping {
+    /// `### Added` subsections, with `#### cli` subsections for the scoped
+    /// changes
+    #[default]
+    Category,
+    /// `### cli` subsections, with the category in front of the entries
+    Scope,
+    /// `### jane` subsections, with the category and scope in front of the
+    /// entries
+    Author,
+    /// A single chronological list, with the category and scope in front
+    /// of the entries
+    Flat,
+}
+
+/// Where the authors of the changes are credited in the changelog
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Attribution {
+    /// After every entry, eg. `- Fixed a crash (by jane)`
+    #[default]
+    Entry,
+    /// In a list of contributors at the end of the unreleased section
+    Contributors,
+    /// Both after every entry and in the list of contributors
+    Both,
+}
+
+/// How an author is credited in the changelog
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AuthorInfo {
+    /// Name to show in the changelog, eg. `@jane`. Defaults to the key
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Link to the profile of the author, eg. `https://github.com/jane`
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Other git names and emails of the author
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+/// Personal defaults from the user-level configuration file, which the
+/// project configuration takes precedence over
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserConfig {
+    /// Name to credit as the author of new tinychanges, instead of the git author
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Whether to write messages of new tinychanges in the external editor
+    #[serde(default)]
+    pub editor: Option<bool>,
+    /// Naming scheme to use when the project doesn't configure one
+    #[serde(default)]
+    pub naming: Option<NameType>,
+}
+
+/// Returns the path to the user-level configuration file
+///
+/// This is `$XDG_CONFIG_HOME/tinychange/config.toml`, falling back to
+/// `~/.config` (or `%APPDATA%` on Windows) when the variable is not set
+pub fn user_config_path() -> Option<PathBuf> {
+    let env_path = |name: &str| {
+        std::env::var_os(name)
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let base = env_path("XDG_CONFIG_HOME").or_else(|| {
+        if cfg!(windows) {
+            env_path("APPDATA")
+        } else {
+            env_path("HOME").map(|home| home.join(".config"))
+        }
+    })?;
+
+    Some(base.join("tinychange").join("config.toml"))
+}
+
+/// Reads the user-level configuration file, if there is one
+pub fn load_user_config() -> miette::Result<UserConfig> {
+    let Some(path) = user_config_path().filter(|path| path.is_file()) else {
+        return Ok(UserConfig::default());
+    };
+
+    let content = fs_err::read_to_string(&path).into_diagnostic()?;
+    validate::from_toml(&path, &content)
+}
+
+/// Looks up an environment variable in `env_vars`, or in the environment of
+/// the process when they are not given
+fn env_var(env_vars: Option<&HashMap<String, String>>, name: &str) -> Option<String> {
+    match env_vars {
+        Some(env_vars) => env_vars.get(name).cloned(),
+        None => std::env::var(name).ok(),
+    }
+}
+
+/// Reads the `TINYCHANGE_*` environment variable override, ignoring empty values
+fn env_override<T>(
+    env_vars: Option<&HashMap<String, String>>,
+    name: &str,
+    expected: &str,
+    parse: impl FnOnce(&str) -> Option<T>,
+) -> miette::Result<Option<T>> {
+    let Some(value) = env_var(env_vars, name) else {
+        return Ok(None);
+    };
+    let value = value.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    match parse(value) {
+        Some(value) => Ok(Some(value)),
+        None => bail!(
+            "Invalid {} environment variable, expected {}: {}",
+            name,
+            expected,
+            value
+        ),
+    }
+}
+
+/// Change types that are mapped to categories when not overridden by the
+/// `category_map`, provided the target category is configured
+const DEFAULT_CATEGORY_MAP: &[(&str, &str)] = &[
+    ("feat", "Added"),
+    ("feature", "Added"),
+    ("add", "Added"),
+    ("fix", "Fixed"),
+    ("bugfix", "Fixed"),
+    ("perf", "Changed"),
+    ("refactor", "Changed"),
+    ("breaking", "Changed"),
+    ("deprecate", "Deprecated"),
+    ("deprecation", "Deprecated"),
+    ("remove", "Removed"),
+    ("removal", "Removed"),
+    ("revert", "Removed"),
+    ("security", "Security"),
+    ("major", "Changed"),
+    ("minor", "Added"),
+    ("patch", "Fixed"),
+];
+
+pub const CONFIG_FILE_NAME: &str = "tinychange.toml";
+
+/// Files that can hold the configuration, in the order of priority
+const CONFIG_FILES: &[&str] = &[CONFIG_FILE_NAME, "Cargo.toml", "package.json"];
+
+/// Parses the configuration from the content of the given file
+///
+/// Besides `tinychange.toml`, the configuration can be embedded in
+/// `Cargo.toml` under `[package.metadata.tinychange]` or
+/// `[workspace.metadata.tinychange]`, and in `package.json` under the
+/// `tinychange` key
+pub fn parse_config(path: &Path, content: &str) -> miette::Result<Config> {
+    match path.file_name().and_then(|name| name.to_str()) {
+        Some("Cargo.toml") => {
+            let Some(config) = cargo_metadata(content)? else {
+                bail!(
+                    "No [package.metadata.tinychange] or [workspace.metadata.tinychange] table in {}",
+                    path.display()
+                );
+            };
+            let config = config.try_into().into_diagnostic()?;
+            validate::validate(&config)?;
+            Ok(config)
+        }
+        Some("package.json") => {
+            let Some(config) = package_json_config(content)? else {
+                bail!("No `tinychange` key in {}", path.display());
+            };
+            let config = serde_json::from_value(config).into_diagnostic()?;
+            validate::validate(&config)?;
+            Ok(config)
+        }
+        _ => validate::parse_toml(path, content),
+    }
+}
+
+fn cargo_metadata(content: &str) -> miette::Result<Option<toml::Value>> {
+    let mut manifest: toml::Table = toml::from_str(content).into_diagnostic()?;
+    Ok(["package", "workspace"].iter().find_map(|table| {
+        manifest
+            .get_mut(*table)?
+            .get_mut("metadata")?
+            .as_table_mut()?
+            .remove("tinychange")
+    }))
+}
+
+fn package_json_config(content: &str) -> miette::Result<Option<serde_json::Value>> {
+    let mut package: serde_json::Value = serde_json::from_str(content).into_diagnostic()?;
+    Ok(package
+        .as_object_mut()
+        .and_then(|package| package.remove("tinychange")))
+}
+
+/// Checks whether the file holds tinychange configuration
+fn has_config(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    if !path.is_file() {
+        return false;
+    }
+    if name == CONFIG_FILE_NAME {
+        return true;
+    }
+
+    let Ok(content) = fs_err::read_to_string(path) else {
+        return false;
+    };
+    match name {
+        "Cargo.toml" => cargo_metadata(&content).is_ok_and(|config| config.is_some()),
+        "package.json" => package_json_config(&content).is_ok_and(|config| config.is_some()),
+        _ => false,
+    }
 }
 
+/// Finds the configuration file in the given directory or its parents, the
+/// same way cargo finds `Cargo.toml`
+///
+/// The search stops at the root of the git repository
+pub fn find_config(start: &Path) -> Option<PathBuf> {
+    for dir in start.ancestors() {
+        if let Some(path) = CONFIG_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| has_config(path))
+        {
+            return Some(path);
+        }
+
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+
+    None
+}
+
+impl Config {
+    /// Creates a configuration with the default values of all the optional
+    /// settings, with paths relative to the configuration directory
+    pub fn new(
+        tinylogs_dir: impl Into<PathBuf>,
+        changelog: impl Into<PathBuf>,
+        categories: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self {
+            tinylogs_dir: tinylogs_dir.into(),
+            changelog: changelog.into(),
+            categories: categories.into_iter().map(Into::into).collect(),
+            naming: None,
+            max_filename_length: None,
+            naming_seed: None,
+            category_map: BTreeMap::new(),
+            extension: None,
+            ignore: vec![],
+            directory_scopes: false,
+            authors: BTreeMap::new(),
+            hidden_authors: vec![],
+            hidden_author_credit: None,
+            attribution: Attribution::default(),
+            contributors_title: None,
+            flag_new_contributors: false,
+            grouping: Grouping::default(),
+            unreleased_pattern: None,
+            unreleased_header: None,
+            changelog_pattern: None,
+        }
+    }
+
+    /// Reads and validates the configuration file, which can be a
+    /// `tinychange.toml`, or a `Cargo.toml` or `package.json` embedding it
+    pub fn load(path: &Path) -> miette::Result<Self> {
+        let content = fs_err::read_to_string(path).into_diagnostic()?;
+        parse_config(path, &content)
+    }
+
+    /// Title of the contributors subsection, or `None` if the authors are
+    /// only credited after the entries
+    pub fn contributors_title(&self) -> Option<&str> {
+        match self.attribution {
+            Attribution::Entry => None,
+            Attribution::Contributors | Attribution::Both => {
+                Some(self.contributors_title.as_deref().unwrap_or("Contributors"))
+            }
+        }
+    }
+
+    /// Matches the header of the unreleased section
+    ///
+    /// Fails if `unreleased_pattern` is not a valid regex
+    pub fn unreleased_section(&self) -> miette::Result<Regex> {
+        section_regex(
+            "unreleased_pattern",
+            self.unreleased_pattern.as_deref().unwrap_or("unreleased"),
+        )
+    }
+
+    /// Matches the header of the section the unreleased section is created
+    /// under
+    ///
+    /// Fails if `changelog_pattern` is not a valid regex
+    pub fn changelog_section(&self) -> miette::Result<Regex> {
+        section_regex(
+            "changelog_pattern",
+            self.changelog_pattern.as_deref().unwrap_or("changelog"),
+        )
+    }
+
+    /// Header of the unreleased section created when the changelog has none
+    pub fn unreleased_header(&self) -> &str {
+        self.unreleased_header
+            .as_deref()
+            .unwrap_or("## [Unreleased]")
+            .trim()
+    }
+
+    /// Titles of the subsections tinychange writes into the unreleased
+    /// section: the categories, followed by the contributors
+    pub fn section_titles(&self) -> Vec<String> {
+        self.categories
+            .iter()
+            .cloned()
+            .chain(self.contributors_title().map(str::to_owned))
+            .collect()
+    }
+
+    /// Finds the author by the key in the `authors` table or one of the
+    /// aliases, ignoring case
+    ///
+    /// Returns the key along with the author info
+    pub fn find_author(&self, name_or_email: &str) -> Option<(&str, &AuthorInfo)> {
+        let name_or_email = name_or_email.trim();
+        self.authors
+            .iter()
+            .find(|(key, info)| {
+                key.eq_ignore_ascii_case(name_or_email)
+                    || info
+                        .aliases
+                        .iter()
+                        .any(|alias| alias.eq_ignore_ascii_case(name_or_email))
+            })
+            .map(|(key, info)| (key.as_str(), info))
+    }
+
+    /// Compiles the `hidden_authors` patterns, to be passed to
+    /// [Config::is_hidden_author] and [Config::credit]
+    ///
+    /// Fails if one of the patterns is not a valid glob
+    pub(crate) fn hidden_author_globs(&self) -> miette::Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.hidden_authors {
+            builder.add(
+                author_pattern(pattern)
+                    .into_diagnostic()
+                    .with_context(|| format!("Invalid `hidden_authors` pattern `{}`", pattern))?,
+            );
+        }
+        builder.build().into_diagnostic()
+    }
+
+    /// Whether the author matches one of the compiled `hidden_authors`
+    /// patterns, by the name in the tinychange or the key in the `authors` table
+    pub(crate) fn is_hidden_author(&self, author: &str, hidden_authors: &GlobSet) -> bool {
+        let author = author.trim();
+        let key = self.find_author(author).map(|(key, _)| key);
+        hidden_authors.is_match(author) || key.is_some_and(|key| hidden_authors.is_match(key))
+    }
+
+    /// Renders the credit for the author, like `[@jane](https://github.com/jane)`
+    /// for authors with a profile link
+    ///
+    /// Returns `None` for hidden authors, unless `hidden_author_credit` is set
+    pub(crate) fn credit<'a>(
+        &'a self,
+        author: &'a str,
+        hidden_authors: &GlobSet,
+    ) -> Option<Cow<'a, str>> {
+        if self.is_hidden_author(author, hidden_authors) {
+            return self.hidden_author_credit.as_deref().map(Cow::Borrowed);
+        }
+
+        let Some((key, info)) = self.find_author(author) else {
+            return Some(Cow::Borrowed(author));
+        };
+
+        let name = info.name.as_deref().unwrap_or(key);
+        Some(match &info.url {
+            Some(url) => Cow::Owned(format!("[{}]({})", name, url)),
+            None => Cow::Borrowed(name),
+        })
+    }
+}
+
+fn section_regex(option: &str, pattern: &str) -> miette::Result<Regex> {
+    regex_for_section_pattern(pattern)
+        .into_diagnostic()
+        .with_context(|| format!("Invalid `{}` in the configuration", option))
+}
+
+/// Compiles the `hidden_authors` pattern, which matches the whole name
+/// ignoring case
+///
+/// Backslash escapes are enabled on every platform, so `*\[bot\]` matches
+/// the literal brackets
+pub(crate) fn author_pattern(pattern: &str) -> Result<Glob, globset::Error> {
+    GlobBuilder::new(pattern.trim())
+        .case_insensitive(true)
+        .backslash_escape(true)
+        .build()
+}
+
+/// Resolved settings shared by all the commands
+///
+/// Combines the project configuration with the user configuration and the
+/// `TINYCHANGE_*` environment variables. Use [CommandOpts::builder] to
+/// create one
 #[derive(Debug)]
 pub struct CommandOpts {
-    silent: bool,
+    reporter: SharedReporter,
     interactive: bool,
+    config_dir: PathBuf,
     tinychanges_dir: PathBuf,
     changelog: PathBuf,
     workdir: PathBuf,
     command_name: String,
+    naming: NameType,
+    naming_seed: Option<u64>,
+    author: Option<(String, &'static str)>,
+    editor: bool,
+    env_vars: Option<HashMap<String, String>>,
     config: Config,
 }
 
-impl CommandOpts {
-    pub fn new(
-        silent: bool,
-        interactive: bool,
-        config_dir: PathBuf,
-        workdir: PathBuf,
-        command_name: String,
-        config: Config,
-    ) -> miette::Result<Self> {
+/// Builder for [CommandOpts]
+///
+/// By default, prompts and output are disabled and the user configuration is
+/// not loaded, which suits calling tinychange from other programs
+#[derive(Debug, Clone)]
+pub struct CommandOptsBuilder {
+    config_dir: PathBuf,
+    config: Config,
+    workdir: Option<PathBuf>,
+    reporter: SharedReporter,
+    interactive: bool,
+    command_name: String,
+    user_config: UserConfig,
+    env_vars: Option<HashMap<String, String>>,
+}
+
+impl CommandOptsBuilder {
+    /// Directory to run git in, defaults to the configuration directory
+    pub fn workdir(mut self, workdir: impl Into<PathBuf>) -> Self {
+        self.workdir = Some(workdir.into());
+        self
+    }
+
+    /// Where to send the progress messages, discarded by default
+    ///
+    /// Use [ConsoleReporter](crate::ConsoleReporter) to print them to the terminal
+    pub fn reporter(mut self, reporter: impl Reporter + 'static) -> Self {
+        self.reporter = SharedReporter(Arc::new(reporter));
+        self
+    }
+
+    /// Whether to prompt the user for missing values
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Name of the command, used in hints like "Run `tinychange init`"
+    pub fn command_name(mut self, command_name: impl Into<String>) -> Self {
+        self.command_name = command_name.into();
+        self
+    }
+
+    /// Personal defaults to use, see [load_user_config]
+    pub fn user_config(mut self, user_config: UserConfig) -> Self {
+        self.user_config = user_config;
+        self
+    }
+
+    /// Environment variables to use instead of the ones of the process
+    pub fn env_vars(mut self, env_vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env_vars = Some(env_vars.into_iter().collect());
+        self
+    }
+
+    /// Resolves the settings, applying the `TINYCHANGE_*` environment variables
+    pub fn build(self) -> miette::Result<CommandOpts> {
+        let Self {
+            config_dir,
+            config,
+            workdir,
+            reporter,
+            interactive,
+            command_name,
+            user_config,
+            env_vars,
+        } = self;
+        let env = env_vars.as_ref();
+        let workdir = workdir.unwrap_or_else(|| config_dir.clone());
+
         let tinylogs_dir = config_dir.join(&config.tinylogs_dir);
         let changelog = config_dir.join(&config.changelog);
 
@@ -46,53 +619,213 @@ impl CommandOpts {
             bail!("Changelog file is outside of the project directory");
         }
 
-        Ok(Self {
-            silent,
+        // environment variables take precedence over the project
+        // configuration, which takes precedence over the user configuration
+        let naming = env_override(env, "TINYCHANGE_NAMING", "a naming scheme", |naming| {
+            NameType::deserialize(toml::Value::String(naming.to_owned())).ok()
+        })?
+        .or_else(|| config.naming.clone())
+        .or(user_config.naming)
+        .unwrap_or_default();
+
+        let naming_seed =
+            env_override(env, "TINYCHANGE_SEED", "a number", |seed| seed.parse().ok())?
+                .or(config.naming_seed);
+
+        let author = env_override(env, "TINYCHANGE_AUTHOR", "a name", |author| {
+            Some(author.to_owned())
+        })?
+        .map(|author| (author, "TINYCHANGE_AUTHOR environment variable"))
+        .or_else(|| {
+            user_config
+                .author
+                .filter(|author| !author.trim().is_empty())
+                .map(|author| (author, "user configuration"))
+        });
+
+        let editor = env_override(
+            env,
+            "TINYCHANGE_EDITOR",
+            "true or false",
+            |editor| match editor.to_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Some(true),
+                "0" | "false" | "no" | "off" => Some(false),
+                _ => None,
+            },
+        )?
+        .or(user_config.editor)
+        .unwrap_or(false);
+
+        Ok(CommandOpts {
+            reporter,
             interactive,
+            config_dir,
             tinychanges_dir: tinylogs_dir,
             changelog,
             workdir,
             command_name,
+            naming,
+            naming_seed,
+            author,
+            editor,
+            env_vars,
             config,
         })
     }
 }
 
 impl CommandOpts {
-    pub fn println(&self, message: &str) {
-        if !self.silent {
-            println!("{}", message);
+    /// Starts building the settings for the project configuration, with
+    /// paths in it relative to `config_dir`
+    pub fn builder(config_dir: impl Into<PathBuf>, config: Config) -> CommandOptsBuilder {
+        CommandOptsBuilder {
+            config_dir: config_dir.into(),
+            config,
+            workdir: None,
+            reporter: SharedReporter(Arc::new(NullReporter)),
+            interactive: false,
+            command_name: "tinychange".to_owned(),
+            user_config: UserConfig::default(),
+            env_vars: None,
         }
     }
 
+    /// Returns the project configuration
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Reports the message to the configured [Reporter]
+    pub fn report(&self, level: Level, message: &str) {
+        self.reporter.0.report(level, message);
+    }
+
+    /// Reports a warning
+    pub fn warn(&self, message: &str) {
+        self.report(Level::Warn, message);
+    }
+
+    /// Reports a progress message
+    pub fn info(&self, message: &str) {
+        self.report(Level::Info, message);
+    }
+
+    /// Reports a message that is only shown with `-v`
+    pub fn debug(&self, message: &str) {
+        self.report(Level::Debug, message);
+    }
+
+    /// Whether the user can be prompted for input
     pub fn interactive(&self) -> bool {
         self.interactive
     }
 
+    /// Configured change categories, in the order of the changelog
     pub fn categories(&self) -> &[String] {
         &self.config.categories
     }
 
+    /// Maps an external change type to one of the configured categories
+    ///
+    /// Looks up the `category_map` first, then the categories themselves and
+    /// then the built-in defaults, ignoring case
+    pub fn map_category(&self, kind: &str) -> Option<&str> {
+        let find_category = |name: &str| {
+            self.categories()
+                .iter()
+                .find(|category| category.eq_ignore_ascii_case(name))
+                .map(String::as_str)
+        };
+
+        if let Some((_, category)) = self
+            .config
+            .category_map
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(kind))
+        {
+            return find_category(category);
+        }
+
+        find_category(kind).or_else(|| {
+            DEFAULT_CATEGORY_MAP
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(kind))
+                .and_then(|(_, category)| find_category(category))
+        })
+    }
+
+    /// Directory to run git in
     pub fn workdir(&self) -> &Path {
         self.workdir.as_path()
     }
 
+    /// Directory containing the config file, which the configured paths are relative to
+    pub fn config_dir(&self) -> &Path {
+        self.config_dir.as_path()
+    }
+
+    /// Directory where the tinychange files are stored
     pub fn tinychanges_dir(&self) -> &Path {
         self.tinychanges_dir.as_path()
     }
 
+    /// Path to the changelog file
     pub fn changelog_file(&self) -> &Path {
         self.changelog.as_path()
     }
 
+    /// How the tinychange files are named
     pub fn naming(&self) -> &NameType {
-        &self.config.naming
+        &self.naming
+    }
+
+    /// Returns the author configured by the user, along with a description of where it came from
+    pub fn author(&self) -> Option<(&str, &str)> {
+        self.author
+            .as_ref()
+            .map(|(author, source)| (author.as_str(), *source))
+    }
+
+    /// Whether messages should be written in the external editor
+    pub fn editor(&self) -> bool {
+        self.editor
+    }
+
+    /// Fixed seed for the generated filenames, if any
+    pub fn naming_seed(&self) -> Option<u64> {
+        self.naming_seed
+    }
+
+    /// Reads an environment variable, from the ones given to the builder if any
+    pub(crate) fn env_var(&self, name: &str) -> Option<String> {
+        env_var(self.env_vars.as_ref(), name)
+    }
+
+    /// Extension of the tinychange files, without the leading dot
+    pub fn extension(&self) -> &str {
+        self.config
+            .extension
+            .as_deref()
+            .map(|extension| extension.trim_start_matches('.'))
+            .unwrap_or("md")
+    }
+
+    /// Glob patterns of the files in the tinychanges directory that are not tinychanges
+    pub fn ignore(&self) -> &[String] {
+        &self.config.ignore
+    }
+
+    /// Whether the subdirectories of the tinychanges directory are scopes
+    pub fn directory_scopes(&self) -> bool {
+        self.config.directory_scopes
     }
 
+    /// Maximum length of the generated filenames, excluding the extension
     pub fn max_filename_length(&self) -> Option<usize> {
         self.config.max_filename_length
     }
 
+    /// Name the tool was invoked as, used in the hints for the user
     pub fn command_name(&self) -> &str {
         &self.command_name
     }
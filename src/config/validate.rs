@@ -0,0 +1,344 @@
+use super::{Config, Grouping};
+use crate::changelog::check_section_pattern;
+use crate::naming::NameType;
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceSpan};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::ops::Range;
+use std::path::Path;
+use thiserror::Error;
+use toml::Spanned;
+
+/// Length of the `-1a2b3c4` hash suffix of generated filenames
+const HASH_SUFFIX_LENGTH: usize = 8;
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("{message}")]
+pub struct InvalidConfig {
+    message: String,
+    #[source_code]
+    src: NamedSource<String>,
+    #[label(collection)]
+    labels: Vec<LabeledSpan>,
+}
+
+/// Part of the configuration a problem was found in
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Location {
+    Categories,
+    Category(usize),
+    MaxFilenameLength,
+    Naming,
+    Extension,
+    Ignore(usize),
+    HiddenAuthor(usize),
+    ContributorsTitle,
+    Grouping,
+    UnreleasedPattern,
+    UnreleasedHeader,
+    ChangelogPattern,
+}
+
+#[derive(Debug, Clone)]
+struct Problem {
+    location: Location,
+    message: String,
+}
+
+/// Same fields as [Config], with spans for locating the problems
+#[derive(Debug, Deserialize)]
+struct SpannedConfig {
+    categories: Spanned<Vec<Spanned<String>>>,
+    max_filename_length: Option<Spanned<usize>>,
+    naming: Option<Spanned<toml::Value>>,
+    extension: Option<Spanned<String>>,
+    #[serde(default)]
+    ignore: Vec<Spanned<String>>,
+    #[serde(default)]
+    hidden_authors: Vec<Spanned<String>>,
+    contributors_title: Option<Spanned<String>>,
+    grouping: Option<Spanned<toml::Value>>,
+    unreleased_pattern: Option<Spanned<String>>,
+    unreleased_header: Option<Spanned<String>>,
+    changelog_pattern: Option<Spanned<String>>,
+}
+
+fn problems(config: &Config) -> Vec<Problem> {
+    let mut problems = vec![];
+
+    if config.categories.is_empty() {
+        problems.push(Problem {
+            location: Location::Categories,
+            message: "At least one category is required".to_string(),
+        });
+    }
+
+    for (idx, category) in config.categories.iter().enumerate() {
+        if category.trim().is_empty() {
+            problems.push(Problem {
+                location: Location::Category(idx),
+                message: "Category name can't be empty".to_string(),
+            });
+        } else if config.categories[..idx]
+            .iter()
+            .any(|other| other.eq_ignore_ascii_case(category))
+        {
+            problems.push(Problem {
+                location: Location::Category(idx),
+                message: format!("Duplicate category `{}`", category),
+            });
+        }
+    }
+
+    if let Some(max_length) = config.max_filename_length {
+        if max_length < HASH_SUFFIX_LENGTH {
+            problems.push(Problem {
+                location: Location::MaxFilenameLength,
+                message: format!(
+                    "Must be at least {} to fit the hash suffix of the filename",
+                    HASH_SUFFIX_LENGTH
+                ),
+            });
+        }
+    }
+
+    // every retry on a taken filename would produce the same name again
+    if let Some(NameType::Template(template)) = &config.naming {
+        if !template.contains("{hash}") {
+            problems.push(Problem {
+                location: Location::Naming,
+                message: "Naming template must contain the `{hash}` placeholder".to_string(),
+            });
+        }
+    }
+
+    if let Some(extension) = &config.extension {
+        let extension = extension.trim_start_matches('.');
+        if extension.is_empty() || sanitise_file_name::sanitise(extension) != extension {
+            problems.push(Problem {
+                location: Location::Extension,
+                message: "Must be a valid file extension, such as `md`".to_string(),
+            });
+        }
+    }
+
+    for (idx, pattern) in config.ignore.iter().enumerate() {
+        if let Err(err) = crate::ignore::check_pattern(pattern) {
+            problems.push(Problem {
+                location: Location::Ignore(idx),
+                message: format!("Invalid glob pattern: {}", err.kind()),
+            });
+        }
+    }
+
+    for (idx, pattern) in config.hidden_authors.iter().enumerate() {
+        if let Err(err) = crate::config::author_pattern(pattern) {
+            problems.push(Problem {
+                location: Location::HiddenAuthor(idx),
+                message: format!("Invalid glob pattern: {}", err.kind()),
+            });
+        }
+    }
+
+    // the title is a single header line, and a category of the same name
+    // would get its entries mixed with the contributors
+    if let Some(title) = &config.contributors_title {
+        if title.trim().is_empty() || title.contains('\n') || title.trim().starts_with('#') {
+            problems.push(Problem {
+                location: Location::ContributorsTitle,
+                message: "Must be a single line of text, without the leading `#`".to_string(),
+            });
+        } else if config
+            .categories
+            .iter()
+            .any(|category| category.trim().eq_ignore_ascii_case(title.trim()))
+        {
+            problems.push(Problem {
+                location: Location::ContributorsTitle,
+                message: format!("Can't be the same as the category `{}`", title.trim()),
+            });
+        }
+    }
+
+    // the scopes are only read from the subdirectories of the tinychanges
+    if config.grouping == Grouping::Scope && !config.directory_scopes {
+        problems.push(Problem {
+            location: Location::Grouping,
+            message: "Grouping by scope requires `directory_scopes = true`".to_string(),
+        });
+    }
+
+    for (location, pattern) in [
+        (Location::UnreleasedPattern, &config.unreleased_pattern),
+        (Location::ChangelogPattern, &config.changelog_pattern),
+    ] {
+        if let Some(Err(err)) = pattern.as_deref().map(check_section_pattern) {
+            problems.push(Problem {
+                location,
+                message: format!("Invalid regex: {}", err),
+            });
+        }
+    }
+
+    // a header the unreleased section isn't found by would be added on every merge
+    if let Some(header) = &config.unreleased_header {
+        if !header.trim().starts_with('#') {
+            problems.push(Problem {
+                location: Location::UnreleasedHeader,
+                message: "Must be a markdown header, such as `## [Unreleased]`".to_string(),
+            });
+        } else if config
+            .unreleased_section()
+            .is_ok_and(|section| !section.is_match(header.trim()))
+        {
+            problems.push(Problem {
+                location: Location::UnreleasedHeader,
+                message: "Must match the `unreleased_pattern`".to_string(),
+            });
+        }
+    }
+
+    problems
+}
+
+fn invalid_config(
+    path: &Path,
+    content: &str,
+    message: String,
+    labels: Vec<LabeledSpan>,
+) -> InvalidConfig {
+    // show paths relative to the working directory, as the user would type them
+    let path = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| path.strip_prefix(cwd).ok())
+        .unwrap_or(path);
+
+    InvalidConfig {
+        message,
+        src: NamedSource::new(path.display().to_string(), content.to_owned()),
+        labels,
+    }
+}
+
+fn label(message: impl Into<String>, span: Range<usize>) -> LabeledSpan {
+    LabeledSpan::new_with_span(Some(message.into()), SourceSpan::from(span))
+}
+
+/// Parses a TOML configuration file, pointing at the problematic part of
+/// the file in case of an error
+pub fn from_toml<T: DeserializeOwned>(path: &Path, content: &str) -> miette::Result<T> {
+    toml::from_str(content).map_err(|err| {
+        let labels = err
+            .span()
+            .map(|span| vec![label(err.message(), span)])
+            .unwrap_or_default();
+        invalid_config(
+            path,
+            content,
+            "Failed to parse configuration file".to_string(),
+            labels,
+        )
+        .into()
+    })
+}
+
+/// Parses and validates `tinychange.toml`, pointing at the problematic parts
+/// of the file in case of an error
+pub fn parse_toml(path: &Path, content: &str) -> miette::Result<Config> {
+    let config: Config = from_toml(path, content)?;
+
+    let problems = problems(&config);
+    if problems.is_empty() {
+        return Ok(config);
+    }
+
+    let spanned: SpannedConfig = from_toml(path, content)?;
+    let labels = problems
+        .into_iter()
+        .map(|problem| {
+            let span = match problem.location {
+                Location::Categories => spanned.categories.span(),
+                Location::Category(idx) => spanned.categories.get_ref()[idx].span(),
+                Location::MaxFilenameLength => spanned
+                    .max_filename_length
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+                Location::Naming => spanned
+                    .naming
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+                Location::Extension => spanned
+                    .extension
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+                Location::Ignore(idx) => spanned.ignore[idx].span(),
+                Location::HiddenAuthor(idx) => spanned.hidden_authors[idx].span(),
+                Location::ContributorsTitle => spanned
+                    .contributors_title
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+                Location::Grouping => spanned
+                    .grouping
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+                Location::UnreleasedPattern => spanned
+                    .unreleased_pattern
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+                Location::UnreleasedHeader => spanned
+                    .unreleased_header
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+                Location::ChangelogPattern => spanned
+                    .changelog_pattern
+                    .as_ref()
+                    .map(Spanned::span)
+                    .unwrap_or_default(),
+            };
+            label(problem.message, span)
+        })
+        .collect();
+
+    Err(invalid_config(path, content, "Invalid configuration".to_string(), labels).into())
+}
+
+/// Validates configuration embedded in other files, where the locations of
+/// the problems are not available
+pub fn validate(config: &Config) -> miette::Result<()> {
+    let problems = problems(config);
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    let problems = problems
+        .iter()
+        .map(|problem| match problem.location {
+            Location::Categories => format!("- categories: {}", problem.message),
+            Location::Category(idx) => format!("- categories[{}]: {}", idx, problem.message),
+            Location::MaxFilenameLength => {
+                format!("- max_filename_length: {}", problem.message)
+            }
+            Location::Naming => format!("- naming: {}", problem.message),
+            Location::Extension => format!("- extension: {}", problem.message),
+            Location::Ignore(idx) => format!("- ignore[{}]: {}", idx, problem.message),
+            Location::HiddenAuthor(idx) => {
+                format!("- hidden_authors[{}]: {}", idx, problem.message)
+            }
+            Location::ContributorsTitle => format!("- contributors_title: {}", problem.message),
+            Location::Grouping => format!("- grouping: {}", problem.message),
+            Location::UnreleasedPattern => format!("- unreleased_pattern: {}", problem.message),
+            Location::UnreleasedHeader => format!("- unreleased_header: {}", problem.message),
+            Location::ChangelogPattern => format!("- changelog_pattern: {}", problem.message),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    miette::bail!("Invalid configuration:\n{}", problems)
+}
@@ -0,0 +1,180 @@
+use crate::tinychange::normalize_message;
+use miette::{bail, miette, Context, IntoDiagnostic};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// A pending change fragment written by another changelog tool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment {
+    pub path: PathBuf,
+    /// The type of the change, as named by the tool that created it
+    pub kind: String,
+    pub message: String,
+    pub author: Option<String>,
+}
+
+/// Lists the fragment files in the directory, sorted by name
+fn fragment_files(dir: &Path, extensions: &[&str]) -> miette::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for file in fs_err::read_dir(dir).into_diagnostic()? {
+        let path = file.into_diagnostic()?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() || name.starts_with('.') || name.eq_ignore_ascii_case("README.md") {
+            continue;
+        }
+
+        let extension = path.extension().unwrap_or_default().to_string_lossy();
+        if extensions.is_empty() || extensions.contains(&extension.as_ref()) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read(path: &Path) -> miette::Result<String> {
+    let content = fs_err::read_to_string(path).into_diagnostic()?;
+    Ok(normalize_message(&content))
+}
+
+/// Reads `.changeset/*.md` files created by changesets
+///
+/// The type of the change is the largest semver bump among the packages
+/// listed in the front matter (`major`, `minor` or `patch`)
+pub fn read_changesets(dir: &Path) -> miette::Result<Vec<Fragment>> {
+    const BUMPS: [&str; 3] = ["major", "minor", "patch"];
+
+    fragment_files(dir, &["md"])?
+        .into_iter()
+        .map(|path| {
+            let content = read(&path)?;
+            let (front_matter, message) = content
+                .strip_prefix("---")
+                .and_then(|rest| rest.split_once("\n---"))
+                .ok_or_else(|| miette!("Missing front matter"))
+                .with_context(|| format!("Failed to read changeset at {}", path.display()))?;
+
+            let kind = parse_yaml(front_matter)
+                .with_context(|| format!("Failed to read changeset at {}", path.display()))?
+                .into_values()
+                .filter_map(|bump| BUMPS.iter().position(|b| *b == bump))
+                .min()
+                .map(|idx| BUMPS[idx])
+                .ok_or_else(|| miette!("No package bumps found in the front matter"))
+                .with_context(|| format!("Failed to read changeset at {}", path.display()))?;
+
+            Ok(Fragment {
+                path,
+                kind: kind.to_owned(),
+                message: normalize_message(message),
+                author: None,
+            })
+        })
+        .collect()
+}
+
+/// Reads `<issue>.<type>[.<counter>][.md]` news fragments created for towncrier
+pub fn read_towncrier(dir: &Path) -> miette::Result<Vec<Fragment>> {
+    fragment_files(dir, &[])?
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let name = ["md", "rst", "txt"]
+                .iter()
+                .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+                .unwrap_or(&name);
+
+            let mut parts = name.split('.').collect::<Vec<_>>();
+            if parts.len() > 2 && parts.last().is_some_and(|p| p.parse::<u32>().is_ok()) {
+                parts.pop();
+            }
+            let [.., _, kind] = parts[..] else {
+                bail!(
+                    "Failed to read news fragment at {}: expected a `<issue>.<type>` file name",
+                    path.display()
+                );
+            };
+
+            Ok(Fragment {
+                kind: kind.to_owned(),
+                message: read(&path)?,
+                author: None,
+                path,
+            })
+        })
+        .collect()
+}
+
+/// Reads `.changes/unreleased/*.yaml` files created by changie
+///
+/// The author is taken from the `Author` custom field, if present
+pub fn read_changie(dir: &Path) -> miette::Result<Vec<Fragment>> {
+    fragment_files(dir, &["yaml", "yml"])?
+        .into_iter()
+        .map(|path| {
+            let mut fields = parse_yaml(&read(&path)?)
+                .with_context(|| format!("Failed to read change at {}", path.display()))?;
+            let mut take = |field: &str| {
+                fields
+                    .remove(field)
+                    .filter(|value| !value.is_empty())
+                    .ok_or_else(|| miette!("Missing `{}` field", field))
+                    .with_context(|| format!("Failed to read change at {}", path.display()))
+            };
+
+            let kind = take("kind")?;
+            let message = take("body")?;
+            let author = take("custom.Author")
+                .or_else(|_| take("custom.author"))
+                .ok();
+
+            Ok(Fragment {
+                path,
+                kind,
+                message: normalize_message(&message),
+                author,
+            })
+        })
+        .collect()
+}
+
+/// Parses the YAML document of a fragment file, flattening nested mappings
+/// into `parent.child` keys and keeping the scalar values as strings
+fn parse_yaml(content: &str) -> miette::Result<BTreeMap<String, String>> {
+    let documents = YamlLoader::load_from_str(content).into_diagnostic()?;
+
+    let mut fields = BTreeMap::new();
+    if let Some(document) = documents.into_iter().next() {
+        flatten_yaml(&mut fields, None, document);
+    }
+    Ok(fields)
+}
+
+fn flatten_yaml(fields: &mut BTreeMap<String, String>, key: Option<String>, value: Yaml) {
+    let value = match value {
+        Yaml::Hash(mapping) => {
+            for (child, value) in mapping {
+                let child = match child {
+                    Yaml::String(child) | Yaml::Real(child) => child,
+                    Yaml::Integer(child) => child.to_string(),
+                    Yaml::Boolean(child) => child.to_string(),
+                    _ => continue,
+                };
+                let child = match &key {
+                    Some(key) => format!("{}.{}", key, child),
+                    None => child,
+                };
+                flatten_yaml(fields, Some(child), value);
+            }
+            return;
+        }
+        Yaml::String(value) | Yaml::Real(value) => value.trim().to_owned(),
+        Yaml::Integer(value) => value.to_string(),
+        Yaml::Boolean(value) => value.to_string(),
+        _ => return,
+    };
+    if let Some(key) = key {
+        fields.insert(key, value);
+    }
+}
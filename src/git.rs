@@ -0,0 +1,180 @@
+use miette::{bail, miette, Context, IntoDiagnostic};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Runs the command in the given directory, returning its trimmed output
+///
+/// Returns `None` if the command failed or produced no output
+pub fn run_cmd<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+    workdir: &Path,
+    cmd: &str,
+    args: I,
+) -> Option<String> {
+    let out = std::process::Command::new(cmd)
+        .args(args)
+        .current_dir(workdir)
+        .output()
+        .ok()?;
+
+    if out.status.success() {
+        String::from_utf8(out.stdout)
+            .ok()
+            .map(|x| x.trim().to_owned())
+            .filter(|x| !x.is_empty())
+    } else {
+        None
+    }
+}
+
+/// Runs git with the given arguments, returning its raw output
+///
+/// Unlike [run_cmd], failures are reported as errors including git's stderr
+pub fn git<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
+    workdir: &Path,
+    args: I,
+) -> miette::Result<String> {
+    let out = std::process::Command::new("git")
+        .args(args)
+        .current_dir(workdir)
+        .output()
+        .into_diagnostic()
+        .context("Failed to run git")?;
+
+    if !out.status.success() {
+        bail!(
+            "git exited with {}: {}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr).trim()
+        );
+    }
+
+    String::from_utf8(out.stdout).map_err(|_| miette!("git produced non UTF-8 output"))
+}
+
+#[derive(Debug, Clone)]
+pub struct Commit {
+    pub hash: String,
+    /// Author name, after applying the `.mailmap`
+    pub author: String,
+    /// Author email, after applying the `.mailmap`
+    pub author_email: String,
+    pub message: String,
+}
+
+const FIELD_SEPARATOR: char = '\x1f';
+const RECORD_SEPARATOR: char = '\x1e';
+
+/// Lists non-merge commits in the given revision range, oldest first
+pub fn log(workdir: &Path, range: &str) -> miette::Result<Vec<Commit>> {
+    let out = git(
+        workdir,
+        [
+            "log",
+            "--no-merges",
+            "--reverse",
+            "--format=%H%x1f%aN%x1f%aE%x1f%B%x1e",
+            range,
+            "--",
+        ],
+    )?;
+
+    out.split(RECORD_SEPARATOR)
+        .map(|record| record.trim_start_matches('\n'))
+        .filter(|record| !record.is_empty())
+        .map(|record| {
+            let mut fields = record.splitn(4, FIELD_SEPARATOR);
+            let (Some(hash), Some(author), Some(author_email), Some(message)) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                bail!("Malformed git log output: {:?}", record);
+            };
+            Ok(Commit {
+                hash: hash.to_owned(),
+                author: author.to_owned(),
+                author_email: author_email.to_owned(),
+                message: message.trim().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Lists hashes of commits in the given revision range that touched the given path
+pub fn commits_touching(workdir: &Path, range: &str, path: &Path) -> miette::Result<Vec<String>> {
+    let out = git(
+        workdir,
+        [
+            OsStr::new("log"),
+            OsStr::new("--format=%H"),
+            OsStr::new(range),
+            OsStr::new("--"),
+            path.as_os_str(),
+        ],
+    )?;
+
+    Ok(out.lines().map(|line| line.trim().to_owned()).collect())
+}
+
+/// Splits a `Name <email>` identity, ignoring anything after the email
+fn parse_ident(ident: &str) -> Option<(String, String)> {
+    let (name, rest) = ident.split_once('<')?;
+    let (email, _) = rest.split_once('>')?;
+    Some((name.trim().to_owned(), email.trim().to_owned()))
+}
+
+/// Returns the email of the identity git would author a commit with
+pub fn author_email(workdir: &Path) -> Option<String> {
+    let ident = run_cmd(workdir, "git", ["var", "GIT_AUTHOR_IDENT"])?;
+    parse_ident(&ident).map(|(_, email)| email)
+}
+
+/// Resolves the identity through the `.mailmap` of the repository,
+/// returning the canonical name and email
+pub fn check_mailmap(workdir: &Path, name: &str, email: &str) -> Option<(String, String)> {
+    let ident = run_cmd(
+        workdir,
+        "git",
+        ["check-mailmap", &format!("{} <{}>", name, email)],
+    )?;
+    parse_ident(&ident)
+}
+
+/// Returns the commit times (seconds since the Unix epoch) at which the files
+/// in the directory were added, keyed by their absolute paths
+///
+/// Files are listed with the time of their latest addition. Returns an empty
+/// map outside of a git repository
+pub fn added_times(dir: &Path) -> HashMap<PathBuf, i64> {
+    let Some(root) = run_cmd(dir, "git", ["rev-parse", "--show-toplevel"]) else {
+        return HashMap::new();
+    };
+    let Some(out) = run_cmd(
+        dir,
+        "git",
+        [
+            OsStr::new("log"),
+            OsStr::new("--diff-filter=A"),
+            OsStr::new("--format=%x1e%ct"),
+            OsStr::new("--name-only"),
+            OsStr::new("--"),
+            dir.as_os_str(),
+        ],
+    ) else {
+        return HashMap::new();
+    };
+
+    let mut times = HashMap::new();
+    for record in out.split(RECORD_SEPARATOR) {
+        let mut lines = record.lines().filter(|line| !line.is_empty());
+        let Some(time) = lines.next().and_then(|time| time.trim().parse().ok()) else {
+            continue;
+        };
+        for file in lines {
+            // the log is newest first
+            if let Ok(path) = fs_err::canonicalize(Path::new(&root).join(file)) {
+                times.entry(path).or_insert(time);
+            }
+        }
+    }
+    times
+}
@@ -0,0 +1,87 @@
+use crate::config::CommandOpts;
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use miette::{Context, IntoDiagnostic};
+use std::path::Path;
+
+/// Name of the file in the tinychanges directory listing additional ignore patterns
+pub const IGNORE_FILE_NAME: &str = ".tinychangeignore";
+
+/// Files that are never tinychanges, matched ignoring case
+const DEFAULT_IGNORE: &[&str] = &["README.md"];
+
+/// Patterns of files in the tinychanges directory which are not tinychanges
+///
+/// Patterns follow a subset of the `.gitignore` syntax: a pattern without a
+/// slash matches at any depth, a leading slash anchors it to the tinychanges
+/// directory, and ignoring a directory ignores everything inside it
+#[derive(Debug, Clone)]
+pub struct IgnoreRules {
+    set: GlobSet,
+}
+
+impl IgnoreRules {
+    /// Collects the default patterns, the `ignore` configuration option and
+    /// the patterns from the `.tinychangeignore` file
+    pub fn load(opts: &CommandOpts) -> miette::Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in DEFAULT_IGNORE {
+            add_pattern(&mut builder, pattern, true)?;
+        }
+        for pattern in opts.ignore() {
+            add_pattern(&mut builder, pattern, false)?;
+        }
+
+        let ignore_file = opts.tinychanges_dir().join(IGNORE_FILE_NAME);
+        if ignore_file.exists() {
+            let content = fs_err::read_to_string(&ignore_file).into_diagnostic()?;
+            for line in content.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                add_pattern(&mut builder, line, false)
+                    .with_context(|| format!("Failed to read {}", ignore_file.display()))?;
+            }
+        }
+
+        Ok(Self {
+            set: builder.build().into_diagnostic()?,
+        })
+    }
+
+    /// Checks whether the path, relative to the tinychanges directory, is ignored
+    pub fn is_ignored(&self, path: &Path) -> bool {
+        self.set.is_match(path)
+    }
+}
+
+/// Checks that the pattern is a valid glob
+pub fn check_pattern(pattern: &str) -> Result<(), globset::Error> {
+    Glob::new(pattern.trim_matches('/')).map(|_| ())
+}
+
+fn add_pattern(
+    builder: &mut GlobSetBuilder,
+    pattern: &str,
+    ignore_case: bool,
+) -> miette::Result<()> {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_matches('/');
+    let pattern = if anchored {
+        pattern.to_owned()
+    } else {
+        format!("**/{}", pattern)
+    };
+
+    for pattern in [pattern.clone(), format!("{}/**", pattern)] {
+        let glob = GlobBuilder::new(&pattern)
+            .literal_separator(true)
+            .case_insensitive(ignore_case)
+            .build()
+            .into_diagnostic()
+            .with_context(|| format!("Invalid ignore pattern `{}`", pattern))?;
+        builder.add(glob);
+    }
+
+    Ok(())
+}
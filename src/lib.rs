@@ -1,19 +1,62 @@
-use crate::config::CommandOpts;
+//! Tiny changelog entries that are merged into the changelog on release
+//!
+//! Besides the command line interface ([run]), the operations can be used
+//! directly, for example from a `cargo xtask`:
+//!
+//! ```no_run
+//! use tinychange::{CommandOpts, Config, TinyChange};
+//!
+//! # fn main() -> miette::Result<()> {
+//! let config = Config::load("tinychange.toml".as_ref())?;
+//! let opts = CommandOpts::builder(".", config).build()?;
+//!
+//! let change = TinyChange::new("Added", "Added the xtask", "juh9870");
+//! tinychange::create(&opts, &change)?;
+//!
+//! let merged = tinychange::merge(&opts, false)?;
+//! println!("Merged {} changes", merged.changes.len());
+//! # Ok(())
+//! # }
+//! ```
+
 use clap::{Parser, Subcommand};
+use commands::from_commits::FromCommitsArgs;
+use commands::import::ImportArgs;
 use commands::merge::MergeArgs;
 use commands::new::NewArgs;
+use commands::notes::NotesArgs;
 use miette::{Context, Diagnostic, IntoDiagnostic};
+use std::collections::HashMap;
+use std::io::IsTerminal;
 use std::path::PathBuf;
 use thiserror::Error;
 
+mod changelog;
 mod commands;
 mod config;
+mod fragments;
+mod git;
+mod ignore;
 mod naming;
+mod report;
 mod tinychange;
 
+pub use changelog::merge_changelog;
+pub use commands::from_commits::from_commits;
+pub use commands::merge::{merge, Merged};
+pub use commands::new::create;
+pub use config::{
+    find_config, load_user_config, Attribution, CommandOpts, CommandOptsBuilder, Config, Grouping,
+    UserConfig,
+};
+pub use naming::NameType;
+pub use report::{ConsoleReporter, Level, NullReporter, Reporter};
+pub use tinychange::{MarkdownChange, TinyChange};
+
 #[cfg(test)]
 mod test;
 
+/// Command line arguments of tinychange
 #[derive(Debug, Parser)]
 #[command(
     version,
@@ -21,9 +64,15 @@ mod test;
     about = "A tool for creating tiny changelogs on a fly!"
 )]
 pub struct TinyChangeArgs {
-    /// Disable all interactive prompts
+    /// Disable all interactive prompts (they are also disabled when stdin is not a terminal)
     #[arg(short = 'I', long)]
     non_interactive: bool,
+    /// Print debug messages
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Only print warnings and errors
+    #[arg(short, long, global = true)]
+    quiet: bool,
     #[arg(short, long)]
     config: Option<PathBuf>,
     #[command(subcommand)]
@@ -38,6 +87,14 @@ enum TinyChangeSubcommand {
     Merge(MergeArgs),
     /// Initialize tinychange configuration in the project
     Init,
+    /// Create tinychanges from conventional commits in the git history
+    FromCommits(FromCommitsArgs),
+    /// Import entries of the unreleased changelog section or fragments of other tools as tinychanges
+    Import(ImportArgs),
+    /// Print the release notes of a single version from the changelog
+    Notes(NotesArgs),
+    /// Print the JSON Schema of the configuration file
+    Schema,
 }
 
 #[derive(Debug, Error, Diagnostic)]
@@ -49,19 +106,74 @@ struct ErrorHelp {
     help: String,
 }
 
+/// Runs the command line interface with the parsed arguments
+///
+/// The configuration is looked up from the current directory, and
+/// `command_name` is used in hints like "Run `tinychange init`"
 pub fn run(args: TinyChangeArgs, command_name: &str) -> miette::Result<()> {
+    run_in(args, command_name, config::load_user_config, None)
+}
+
+/// Like [run], but with the given user configuration and environment
+/// variables instead of the ones of the current user and process
+#[cfg(test)]
+pub(crate) fn run_with(
+    args: TinyChangeArgs,
+    command_name: &str,
+    user_config: UserConfig,
+    env_vars: impl IntoIterator<Item = (String, String)>,
+) -> miette::Result<()> {
+    run_in(
+        args,
+        command_name,
+        || Ok(user_config),
+        Some(env_vars.into_iter().collect()),
+    )
+}
+
+fn run_in(
+    args: TinyChangeArgs,
+    command_name: &str,
+    user_config: impl FnOnce() -> miette::Result<UserConfig>,
+    env_vars: Option<HashMap<String, String>>,
+) -> miette::Result<()> {
     let command = args
         .command
         .unwrap_or_else(|| TinyChangeSubcommand::New(Default::default()));
 
-    let config_path = args
-        .config
-        .unwrap_or_else(|| PathBuf::from("tinychange.toml"));
+    // never wait for answers that can't come, like in pipelines
+    let interactive = !args.non_interactive && std::io::stdin().is_terminal();
+
+    let level = if args.verbose {
+        Level::Debug
+    } else if args.quiet {
+        Level::Warn
+    } else {
+        Level::Info
+    };
 
     if matches!(command, TinyChangeSubcommand::Init) {
-        return commands::init::run(config_path, command_name);
+        let config_path = args
+            .config
+            .unwrap_or_else(|| PathBuf::from(config::CONFIG_FILE_NAME));
+        return commands::init::run(
+            config_path,
+            command_name,
+            interactive,
+            &ConsoleReporter::new(level),
+        );
     }
 
+    if matches!(command, TinyChangeSubcommand::Schema) {
+        return commands::schema::run();
+    }
+
+    let workdir = std::env::current_dir().into_diagnostic()?;
+
+    let config_path = args.config.unwrap_or_else(|| {
+        config::find_config(&workdir).unwrap_or_else(|| PathBuf::from(config::CONFIG_FILE_NAME))
+    });
+
     let config = fs_err::read_to_string(&config_path)
         .into_diagnostic()
         .map_err(|e| ErrorHelp {
@@ -72,29 +184,34 @@ pub fn run(args: TinyChangeArgs, command_name: &str) -> miette::Result<()> {
             ),
         })?;
 
-    let config = toml::from_str(&config)
-        .into_diagnostic()
-        .context("Failed to read configuration file")?;
-
-    let workdir = std::env::current_dir().into_diagnostic()?;
+    let config =
+        config::parse_config(&config_path, &config).context("Failed to read configuration file")?;
 
     let config_folder = config_path
         .parent()
         .map(|p| p.to_owned())
         .unwrap_or_else(|| workdir.clone());
 
-    let opts = CommandOpts::new(
-        args.non_interactive,
-        !args.non_interactive,
-        config_folder,
-        workdir,
-        command_name.to_owned(),
-        config,
-    )?;
+    let opts = CommandOpts::builder(config_folder, config)
+        .workdir(workdir)
+        .reporter(ConsoleReporter::new(level))
+        .interactive(interactive)
+        .command_name(command_name)
+        .user_config(user_config()?);
+    let opts = match env_vars {
+        Some(env_vars) => opts.env_vars(env_vars),
+        None => opts,
+    }
+    .build()?;
 
     match command {
         TinyChangeSubcommand::New(cmd) => cmd.run(opts),
         TinyChangeSubcommand::Merge(cmd) => cmd.run(opts),
-        TinyChangeSubcommand::Init => unreachable!("Handled above"),
+        TinyChangeSubcommand::FromCommits(cmd) => cmd.run(opts),
+        TinyChangeSubcommand::Import(cmd) => cmd.run(opts),
+        TinyChangeSubcommand::Notes(cmd) => cmd.run(opts),
+        TinyChangeSubcommand::Init | TinyChangeSubcommand::Schema => {
+            unreachable!("Handled above")
+        }
     }
 }
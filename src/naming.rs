@@ -1,29 +1,78 @@
+use crate::git::run_cmd;
+use crate::tinychange::TinyChange;
 use fake::{faker, Fake};
 use miette::bail;
 use rand::prelude::SmallRng;
 use rand::SeedableRng;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+/// How the filenames of the new tinychanges are generated
+#[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum NameType {
+    /// Random buzzwords, eg. `persistent-tertiary-algorithm-1a2b3c4`
     #[default]
     Buzzword,
+    /// Random lorem ipsum words, eg. `dolor-sit-amet-1a2b3c4`
     Lorem,
+    /// Full hash of the change, eg. `1a2b3c4d5e6f7a8b`
     Hash,
+    /// First words of the message, eg. `fixed-crash-on-startup-1a2b3c4`
+    Slug,
+    /// Current date followed by the message slug, eg. `2025-01-24-fixed-crash-1a2b3c4`
+    Date,
+    /// Current git branch name, eg. `fix-startup-crash-1a2b3c4`
+    Branch,
+    /// Custom format string, with `{kind}`, `{author}`, `{date}`, `{slug}`,
+    /// `{branch}` and `{hash}` placeholders, where `{hash}` is required
+    Template(String),
 }
 
 const MAX_TRIES: usize = 100;
 
+/// Maximum number of message words used for the slug
+const MAX_SLUG_WORDS: usize = 8;
+
 impl NameType {
-    pub fn generate(&self, seed: u64, max_length: Option<usize>) -> miette::Result<String> {
+    /// Generates the filename for the change, without the extension
+    pub(crate) fn generate(
+        &self,
+        change: &TinyChange,
+        seed: u64,
+        max_length: Option<usize>,
+        workdir: &Path,
+    ) -> miette::Result<String> {
         let max_length = max_length.unwrap_or(127);
+        let hash = format!("{:016x}", seed);
+        let short_hash = &hash[..7];
+
+        let template = match self {
+            NameType::Buzzword | NameType::Lorem => {
+                return self.generate_random(seed, short_hash, max_length)
+            }
+            NameType::Hash => return truncate_hash(&hash, short_hash, max_length),
+            NameType::Slug => "{slug}-{hash}",
+            NameType::Date => "{date}-{slug}-{hash}",
+            NameType::Branch => "{branch}-{hash}",
+            NameType::Template(template) => template.as_str(),
+        };
+
+        render_template(template, change, short_hash, max_length, workdir)
+    }
+
+    fn generate_random(
+        &self,
+        seed: u64,
+        short_hash: &str,
+        max_length: usize,
+    ) -> miette::Result<String> {
         let mut rng = &mut SmallRng::seed_from_u64(seed);
         let mut tries = 0;
         loop {
             tries += 1;
-            let hash = format!("{:x}", seed);
-            let short_hash = &hash[..7];
             let name = match self {
                 NameType::Buzzword => {
                     let start: String = faker::company::en::Buzzword().fake_with_rng(&mut rng);
@@ -40,7 +89,7 @@ impl NameType {
                     let word = words.join("-").to_lowercase();
                     format!("{}-{}", word, short_hash)
                 }
-                NameType::Hash => hash,
+                _ => unreachable!("Only random names are re-rolled"),
             };
 
             let name = sanitise_file_name::sanitise(&name);
@@ -59,3 +108,178 @@ impl NameType {
         }
     }
 }
+
+/// Renders the naming template, truncating the message slug to fit into the
+/// maximum length
+fn render_template(
+    template: &str,
+    change: &TinyChange,
+    short_hash: &str,
+    max_length: usize,
+    workdir: &Path,
+) -> miette::Result<String> {
+    if !template.contains("{hash}") {
+        bail!(
+            help = "Add the {hash} placeholder, so that tinychanges with the same message get different names",
+            "Naming template `{}` has no {{hash}} placeholder",
+            template
+        );
+    }
+
+    let mut parts = vec![];
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(end) = rest[start..].find('}') else {
+            bail!("Unclosed placeholder in naming template `{}`", template);
+        };
+        parts.push(Part::Literal(&rest[..start]));
+
+        let part = match &rest[(start + 1)..(start + end)] {
+            "kind" => Part::Value(slugify(&change.kind, true)),
+            "author" => Part::Value(slugify(&change.author, true)),
+            "hash" => Part::Value(short_hash.to_owned()),
+            "date" => Part::Value(today()),
+            "branch" => Part::Value(slugify(&current_branch(workdir).unwrap_or_default(), true)),
+            "slug" => Part::Slug,
+            other => bail!(
+                help = "Available placeholders are {kind}, {author}, {date}, {slug}, {branch} and {hash}",
+                "Unknown placeholder `{{{}}}` in naming template `{}`",
+                other,
+                template
+            ),
+        };
+        parts.push(part);
+        rest = &rest[(start + end + 1)..];
+    }
+    parts.push(Part::Literal(rest));
+
+    let render = |slug: &str| {
+        let name = parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text,
+                Part::Value(value) => value.as_str(),
+                Part::Slug => slug,
+            })
+            .collect::<String>();
+        let name = collapse_dashes(&name);
+        if name.is_empty() {
+            name
+        } else {
+            sanitise_file_name::sanitise(&name)
+        }
+    };
+
+    let mut slug = slugify(&change.message, false);
+    let mut name = render(&slug);
+    // cut whole words off the slug until the name fits, and characters after that
+    while name.len() > max_length && !slug.is_empty() {
+        match slug.rfind('-') {
+            Some(idx) => slug.truncate(idx),
+            None => {
+                let overflow = name.len() - max_length;
+                let mut len = slug.len().saturating_sub(overflow);
+                while !slug.is_char_boundary(len) {
+                    len -= 1;
+                }
+                slug.truncate(len);
+            }
+        }
+        name = render(&slug);
+    }
+
+    if name.is_empty() {
+        bail!("Naming template `{}` produced an empty filename", template);
+    }
+
+    if name.len() > max_length {
+        bail!(
+            help = "Try increasing the `max_filename_length` in your configuration, or use a shorter naming template",
+            "Failed to generate a filename of length {} with naming template `{}`",
+            max_length,
+            template
+        );
+    }
+
+    Ok(name)
+}
+
+/// Cuts the full hash to the maximum length, keeping at least the short hash
+fn truncate_hash(hash: &str, short_hash: &str, max_length: usize) -> miette::Result<String> {
+    if max_length < short_hash.len() {
+        bail!(
+            help = "Try increasing the `max_filename_length` in your configuration",
+            "Failed to generate a filename of length {} from the hash",
+            max_length
+        );
+    }
+    Ok(hash[..max_length.min(hash.len())].to_owned())
+}
+
+enum Part<'a> {
+    Literal(&'a str),
+    Value(String),
+    Slug,
+}
+
+/// Converts the text into a lowercase, dash-separated slug
+///
+/// Unless `whole` is set, only the first words of the first line are used
+fn slugify(text: &str, whole: bool) -> String {
+    let text = if whole {
+        text
+    } else {
+        text.lines().next().unwrap_or_default()
+    };
+
+    let words = text
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty());
+
+    let words: Vec<_> = if whole {
+        words.collect()
+    } else {
+        words.take(MAX_SLUG_WORDS).collect()
+    };
+
+    collapse_dashes(&words.join("-").to_lowercase())
+}
+
+/// Collapses repeated dashes and removes the leading and trailing ones
+fn collapse_dashes(text: &str) -> String {
+    text.split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn current_branch(workdir: &Path) -> Option<String> {
+    run_cmd(workdir, "git", ["rev-parse", "--abbrev-ref", "HEAD"]).filter(|branch| branch != "HEAD")
+}
+
+/// Returns the current UTC date in the `YYYY-MM-DD` format
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86400;
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Converts the number of days since the Unix epoch into a (year, month, day) date
+///
+/// See <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
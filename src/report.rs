@@ -0,0 +1,107 @@
+use owo_colors::OwoColorize;
+use std::fmt::{Debug, Formatter};
+use std::io::IsTerminal;
+use std::sync::Arc;
+
+/// Importance of a reported message
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Level {
+    /// Failure of the command
+    Error,
+    /// Problem that doesn't stop the command
+    Warn,
+    /// Progress of the command
+    Info,
+    /// Details that are only shown with `-v`
+    Debug,
+}
+
+/// Receives the progress messages of the commands
+///
+/// Implemented for closures, so library callers can forward the messages
+/// into their own log:
+///
+/// ```no_run
+/// use tinychange::{CommandOpts, Config, Level};
+///
+/// # fn main() -> miette::Result<()> {
+/// let config = Config::load("tinychange.toml".as_ref())?;
+/// let opts = CommandOpts::builder(".", config)
+///     .reporter(|level: Level, message: &str| eprintln!("[{:?}] {}", level, message))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait Reporter {
+    /// Handles a single message
+    fn report(&self, level: Level, message: &str);
+}
+
+impl<F: Fn(Level, &str)> Reporter for F {
+    fn report(&self, level: Level, message: &str) {
+        self(level, message)
+    }
+}
+
+/// Discards all messages
+#[derive(Debug, Copy, Clone, Default)]
+pub struct NullReporter;
+
+impl Reporter for NullReporter {
+    fn report(&self, _level: Level, _message: &str) {}
+}
+
+/// Prints messages up to the given level to the terminal
+///
+/// Informational messages go to stdout, warnings and errors to stderr.
+/// Output is colored when printing to a terminal, unless the `NO_COLOR`
+/// environment variable is set
+#[derive(Debug, Copy, Clone)]
+pub struct ConsoleReporter {
+    max_level: Level,
+}
+
+impl ConsoleReporter {
+    /// Creates a reporter that prints the messages up to `max_level`
+    pub fn new(max_level: Level) -> Self {
+        Self { max_level }
+    }
+}
+
+impl Reporter for ConsoleReporter {
+    fn report(&self, level: Level, message: &str) {
+        if level > self.max_level {
+            return;
+        }
+
+        match level {
+            Level::Error if use_color(&std::io::stderr()) => {
+                eprintln!("{} {}", "error:".red().bold(), message)
+            }
+            Level::Error => eprintln!("error: {}", message),
+            Level::Warn if use_color(&std::io::stderr()) => {
+                eprintln!("{} {}", "warning:".yellow().bold(), message)
+            }
+            Level::Warn => eprintln!("warning: {}", message),
+            Level::Info => println!("{}", message),
+            Level::Debug if use_color(&std::io::stdout()) => println!("{}", message.dimmed()),
+            Level::Debug => println!("{}", message),
+        }
+    }
+}
+
+/// Checks whether the output is a terminal and colors are not disabled with `NO_COLOR`
+fn use_color(stream: &impl IsTerminal) -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && stream.is_terminal()
+}
+
+/// Shared handle to the reporter, so [crate::CommandOptsBuilder] stays cloneable
+#[derive(Clone)]
+pub(crate) struct SharedReporter(pub(crate) Arc<dyn Reporter>);
+
+impl Debug for SharedReporter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Reporter")
+    }
+}
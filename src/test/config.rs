@@ -0,0 +1,282 @@
+use crate::config::{find_config, parse_config, CommandOpts, Config, UserConfig};
+use crate::naming::NameType;
+use std::path::Path;
+
+#[test]
+fn finds_config_in_parent_directories() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    fs_err::create_dir_all(root.join(".git")).unwrap();
+    fs_err::create_dir_all(root.join("crates/cli/src")).unwrap();
+    fs_err::write(root.join("tinychange.toml"), "").unwrap();
+
+    assert_eq!(
+        find_config(&root.join("crates/cli/src")),
+        Some(root.join("tinychange.toml"))
+    );
+    assert_eq!(find_config(root), Some(root.join("tinychange.toml")));
+
+    fs_err::write(root.join("crates/cli/tinychange.toml"), "").unwrap();
+    assert_eq!(
+        find_config(&root.join("crates/cli/src")),
+        Some(root.join("crates/cli/tinychange.toml"))
+    );
+}
+
+#[test]
+fn stops_at_git_root() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    fs_err::create_dir_all(root.join("repo/.git")).unwrap();
+    fs_err::create_dir_all(root.join("repo/src")).unwrap();
+    fs_err::write(root.join("tinychange.toml"), "").unwrap();
+
+    assert_eq!(find_config(&root.join("repo/src")), None);
+}
+
+#[test]
+fn finds_embedded_config() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    fs_err::create_dir_all(root.join(".git")).unwrap();
+    fs_err::create_dir_all(root.join("crates/cli")).unwrap();
+    fs_err::create_dir_all(root.join("web")).unwrap();
+
+    fs_err::write(
+        root.join("Cargo.toml"),
+        "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.metadata.tinychange]\ntinylogs_dir = \".tinychange\"\n",
+    )
+    .unwrap();
+    fs_err::write(
+        root.join("crates/cli/Cargo.toml"),
+        "[package]\nname = \"cli\"\n",
+    )
+    .unwrap();
+    fs_err::write(root.join("web/package.json"), r#"{ "name": "web" }"#).unwrap();
+
+    assert_eq!(
+        find_config(&root.join("crates/cli")),
+        Some(root.join("Cargo.toml"))
+    );
+    assert_eq!(
+        find_config(&root.join("web")),
+        Some(root.join("Cargo.toml"))
+    );
+
+    fs_err::write(
+        root.join("web/package.json"),
+        r#"{ "name": "web", "tinychange": { "tinylogs_dir": ".tinychange" } }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        find_config(&root.join("web")),
+        Some(root.join("web/package.json"))
+    );
+}
+
+#[test]
+fn parses_embedded_config() {
+    let cargo = parse_config(
+        Path::new("Cargo.toml"),
+        r#"
+[package]
+name = "example"
+
+[package.metadata.tinychange]
+tinylogs_dir = ".tinychange"
+changelog = "CHANGELOG.md"
+categories = ["Added", "Fixed"]
+"#,
+    )
+    .unwrap();
+    assert_eq!(cargo.categories, ["Added", "Fixed"]);
+
+    let package = parse_config(
+        Path::new("package.json"),
+        r#"{
+            "name": "example",
+            "tinychange": {
+                "tinylogs_dir": ".tinychange",
+                "changelog": "CHANGELOG.md",
+                "categories": ["Added", "Fixed"]
+            }
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(package.categories, ["Added", "Fixed"]);
+
+    assert!(parse_config(Path::new("Cargo.toml"), "[package]\nname = \"example\"\n").is_err());
+    assert!(parse_config(Path::new("package.json"), r#"{ "name": "example" }"#).is_err());
+}
+
+#[test]
+fn default_config_is_valid() {
+    let config = parse_config(
+        Path::new("tinychange.toml"),
+        include_str!("../tinychange.default.toml"),
+    )
+    .unwrap();
+
+    let schema = serde_json::to_value(schemars::schema_for!(Config)).unwrap();
+    let properties = schema["properties"].as_object().unwrap();
+    let config = serde_json::to_value(config).unwrap();
+    for key in config.as_object().unwrap().keys() {
+        assert!(
+            properties.contains_key(key),
+            "`{}` is missing from the schema",
+            key
+        );
+    }
+    assert_eq!(schema["additionalProperties"], false);
+}
+
+#[test]
+fn rejects_invalid_extension_and_ignore_patterns() {
+    let parse = |extra: &str| {
+        parse_config(
+            Path::new("tinychange.toml"),
+            &format!(
+                "tinylogs_dir = \".tinychange\"\nchangelog = \"CHANGELOG.md\"\ncategories = [\"Added\"]\n{}",
+                extra
+            ),
+        )
+    };
+
+    assert!(parse("extension = \".txt\"\nignore = [\"archive/\", \"*.draft.md\"]").is_ok());
+    assert!(parse("extension = \"\"").is_err());
+    assert!(parse("extension = \"a/b\"").is_err());
+    assert!(parse("ignore = [\"drafts/[abc\"]").is_err());
+    assert!(parse("hidden_authors = [\"dependabot*\", '*\\[bot\\]']").is_ok());
+    assert!(parse("hidden_authors = [\"bots/[abc\"]").is_err());
+}
+
+#[test]
+fn rejects_invalid_section_patterns() {
+    let parse = |extra: &str| {
+        parse_config(
+            Path::new("tinychange.toml"),
+            &format!(
+                "tinylogs_dir = \".tinychange\"\nchangelog = \"CHANGELOG.md\"\ncategories = [\"Added\"]\n{}",
+                extra
+            ),
+        )
+    };
+
+    assert!(
+        parse("unreleased_pattern = \"upcoming|next\"\nunreleased_header = \"## Next\"").is_ok()
+    );
+    assert!(parse("unreleased_pattern = \"(upcoming\"").is_err());
+    assert!(parse("changelog_pattern = \"release[\"").is_err());
+    assert!(parse("unreleased_header = \"Unreleased\"").is_err());
+    assert!(parse("unreleased_header = \"## Upcoming\"").is_err());
+}
+
+#[test]
+fn rejects_invalid_contributors_title() {
+    let parse = |title: &str| {
+        parse_config(
+            Path::new("tinychange.toml"),
+            &format!(
+                "tinylogs_dir = \".tinychange\"\nchangelog = \"CHANGELOG.md\"\ncategories = [\"Added\"]\ncontributors_title = {}",
+                title
+            ),
+        )
+    };
+
+    assert!(parse("\"Thanks (C++)\"").is_ok());
+    assert!(parse("\" \"").is_err());
+    assert!(parse("\"### Contributors\"").is_err());
+    assert!(parse("\"Thanks\\nto\"").is_err());
+    assert!(parse("\"added\"").is_err());
+}
+
+#[test]
+fn rejects_naming_templates_without_hash() {
+    let parse = |naming: &str| {
+        parse_config(
+            Path::new("tinychange.toml"),
+            &format!(
+                "tinylogs_dir = \".tinychange\"\nchangelog = \"CHANGELOG.md\"\ncategories = [\"Added\"]\nnaming = {}",
+                naming
+            ),
+        )
+    };
+
+    assert!(parse("{ template = \"{date}-{slug}-{hash}\" }").is_ok());
+    assert!(parse("{ template = \"{date}-{slug}\" }").is_err());
+    assert!(parse("\"slug\"").is_ok());
+}
+
+#[test]
+fn rejects_scope_grouping_without_directory_scopes() {
+    let parse = |extra: &str| {
+        parse_config(
+            Path::new("tinychange.toml"),
+            &format!(
+                "tinylogs_dir = \".tinychange\"\nchangelog = \"CHANGELOG.md\"\ncategories = [\"Added\"]\ngrouping = \"scope\"\n{}",
+                extra
+            ),
+        )
+    };
+
+    assert!(parse("directory_scopes = true").is_ok());
+    assert!(parse("").is_err());
+}
+
+fn opts(config: &str, user_config: UserConfig) -> CommandOpts {
+    let config = toml::from_str(config).expect("Should parse the config");
+    CommandOpts::builder("project", config)
+        .user_config(user_config)
+        .env_vars([])
+        .build()
+        .expect("Should create command options")
+}
+
+const CONFIG: &str = r#"
+tinylogs_dir = ".tinychange"
+changelog = "CHANGELOG.md"
+categories = ["Added", "Fixed"]
+"#;
+
+#[test]
+fn project_config_takes_precedence_over_user_config() {
+    let user_config = UserConfig {
+        author: Some("juh9870".to_string()),
+        editor: Some(true),
+        naming: Some(NameType::Slug),
+    };
+
+    let opts = self::opts(CONFIG, user_config.clone());
+    assert_eq!(opts.naming(), &NameType::Slug);
+    assert_eq!(opts.author(), Some(("juh9870", "user configuration")));
+    assert!(opts.editor());
+
+    let opts = self::opts(&format!("{}naming = \"lorem\"", CONFIG), user_config);
+    assert_eq!(opts.naming(), &NameType::Lorem);
+
+    let opts = self::opts(CONFIG, UserConfig::default());
+    assert_eq!(opts.naming(), &NameType::Buzzword);
+    assert_eq!(opts.author(), None);
+    assert!(!opts.editor());
+}
+
+#[test]
+fn environment_takes_precedence_over_configs() {
+    let config = toml::from_str(&format!("{}naming = \"lorem\"", CONFIG)).unwrap();
+    let opts = CommandOpts::builder("project", config)
+        .user_config(UserConfig {
+            author: Some("juh9870".to_string()),
+            ..Default::default()
+        })
+        .env_vars([
+            ("TINYCHANGE_NAMING".to_string(), "slug".to_string()),
+            ("TINYCHANGE_AUTHOR".to_string(), "bob".to_string()),
+        ])
+        .build()
+        .unwrap();
+    assert_eq!(opts.naming(), &NameType::Slug);
+    assert_eq!(
+        opts.author(),
+        Some(("bob", "TINYCHANGE_AUTHOR environment variable"))
+    );
+}
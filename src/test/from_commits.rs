@@ -0,0 +1,110 @@
+use crate::commands::from_commits::ConventionalCommit;
+use crate::test::git;
+use crate::{from_commits, merge, CommandOpts};
+
+fn parse(message: &str) -> Option<ConventionalCommit> {
+    ConventionalCommit::parse(message)
+}
+
+#[test]
+fn parses_conventional_commits() {
+    let commit = parse("feat(cli): add `from-commits` command").unwrap();
+    assert_eq!(commit.kind, "feat");
+    assert!(!commit.breaking);
+    assert_eq!(commit.message(), "Add `from-commits` command");
+
+    let commit = parse("Fix: handle empty ranges").unwrap();
+    assert_eq!(commit.kind, "fix");
+    assert_eq!(commit.message(), "Handle empty ranges");
+}
+
+#[test]
+fn detects_breaking_changes() {
+    assert!(parse("feat!: drop the old format").unwrap().breaking);
+    assert!(parse("refactor(config)!: rename fields").unwrap().breaking);
+    assert!(
+        parse("feat: new format\n\nBREAKING CHANGE: the old format is gone")
+            .unwrap()
+            .breaking
+    );
+}
+
+#[test]
+fn rejects_non_conventional_commits() {
+    assert_eq!(parse("Update README.md"), None);
+    assert_eq!(parse("feat:"), None);
+    assert_eq!(parse("Merge branch 'main': conflicts"), None);
+    assert_eq!(parse(""), None);
+}
+
+#[test]
+fn skips_merged_commits_on_rerun() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    let config = crate::config::parse_config(
+        &root.join("tinychange.toml"),
+        include_str!("../tinychange.default.toml"),
+    )
+    .unwrap();
+    let opts = CommandOpts::builder(root, config)
+        .env_vars([])
+        .build()
+        .unwrap();
+
+    git(root, &["init", "-q"], 0);
+    for (time, message) in [
+        (1_000_000, "feat: add the parser"),
+        (2_000_000, "fix: handle empty input"),
+    ] {
+        git(
+            root,
+            &["commit", "-q", "--allow-empty", "-m", message],
+            time,
+        );
+    }
+
+    assert_eq!(from_commits(&opts, "HEAD").unwrap(), 2);
+    assert_eq!(from_commits(&opts, "HEAD").unwrap(), 0);
+
+    merge(&opts, false).unwrap();
+    assert_eq!(from_commits(&opts, "HEAD").unwrap(), 0);
+    assert!(merge(&opts, false).unwrap().changes.is_empty());
+
+    let changelog = fs_err::read_to_string(root.join("CHANGELOG.md")).unwrap();
+    assert_eq!(changelog.matches("Add the parser").count(), 1);
+}
+
+#[test]
+fn ignores_entries_of_earlier_releases() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    let config = crate::config::parse_config(
+        &root.join("tinychange.toml"),
+        include_str!("../tinychange.default.toml"),
+    )
+    .unwrap();
+    let opts = CommandOpts::builder(root, config)
+        .env_vars([])
+        .build()
+        .unwrap();
+
+    fs_err::write(
+        root.join("CHANGELOG.md"),
+        "# Changelog\n\n## [Unreleased]\n\n### Added\n\n- Add the parser\n\n## [1.0.0] - 2020-01-01\n\n### Fixed\n\n- Handle empty input\n",
+    )
+    .unwrap();
+
+    git(root, &["init", "-q"], 0);
+    for (time, message) in [
+        (1_000_000, "feat: add the parser"),
+        (2_000_000, "fix: handle empty input"),
+    ] {
+        git(
+            root,
+            &["commit", "-q", "--allow-empty", "-m", message],
+            time,
+        );
+    }
+
+    assert_eq!(from_commits(&opts, "HEAD").unwrap(), 1);
+}
@@ -0,0 +1,106 @@
+use crate::changelog::subsection_titles;
+use crate::commands::init::{Preset, Setup, NAMING_SCHEMES};
+use crate::config::parse_config;
+use std::path::Path;
+
+#[test]
+fn generated_configs_are_valid() {
+    let existing = Preset::Existing(vec!["Features".to_owned(), "Bug \"Fixes\"".to_owned()]);
+    for preset in Preset::ALL.into_iter().chain([existing]) {
+        for naming in NAMING_SCHEMES {
+            let setup = Setup {
+                changelog: "docs/CHANGES.md".to_owned(),
+                tinychanges_dir: "changes \"pending\"".to_owned(),
+                naming,
+                preset: preset.clone(),
+            };
+            let config = parse_config(Path::new("tinychange.toml"), &setup.config())
+                .unwrap_or_else(|err| panic!("{:?}", err));
+
+            assert_eq!(config.changelog, Path::new("docs/CHANGES.md"));
+            assert_eq!(config.tinylogs_dir, Path::new("changes \"pending\""));
+            assert_eq!(config.categories, preset.categories());
+            let naming = config
+                .naming
+                .map(|naming| format!("{:?}", naming).to_lowercase());
+            assert_eq!(naming.as_deref().unwrap_or("buzzword"), setup.naming);
+        }
+    }
+}
+
+#[test]
+fn conventional_commits_preset_maps_commit_types() {
+    let setup = Setup {
+        preset: Preset::ConventionalCommits,
+        ..Default::default()
+    };
+    let config = parse_config(Path::new("tinychange.toml"), &setup.config()).unwrap();
+
+    assert_eq!(config.category_map["feat"], "Features");
+    assert_eq!(config.category_map["fix"], "Bug Fixes");
+    assert_eq!(config.category_map["breaking"], "Breaking Changes");
+}
+
+#[test]
+fn infers_categories_by_frequency() {
+    let changelog = "\
+# Changelog
+
+## [Unreleased]
+
+### Documented
+
+## [1.1.0]
+
+### [Fixed]
+
+## [1.0.0]
+
+### Added
+### fixed
+### Security
+";
+    assert_eq!(subsection_titles(changelog), ["Fixed", "Added", "Security"]);
+}
+
+#[test]
+fn infers_categories_of_releases_only() {
+    let changelog = "\
+# Changelog
+
+### How to read this file
+
+## [1.0.0]
+
+### C++ API
+
+#### Details
+
+### Added
+";
+    assert_eq!(subsection_titles(changelog), ["C++ API", "Added"]);
+}
+
+#[test]
+fn infers_categories_without_contributors() {
+    let changelog = "\
+# Changelog
+
+## [1.1.0]
+
+### Fixed
+
+### Contributors
+
+- jane
+
+## [1.0.0]
+
+### Added
+
+### Thanks to
+
+- john
+";
+    assert_eq!(subsection_titles(changelog), ["Fixed", "Added"]);
+}
@@ -0,0 +1,152 @@
+use crate::commands::new::resolve_author;
+use crate::test::{change, git};
+use crate::{create, merge, CommandOpts, Config, Grouping, Level, TinyChange};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn creates_and_merges_changes() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    fs_err::write(
+        root.join("tinychange.toml"),
+        include_str!("../tinychange.default.toml"),
+    )
+    .unwrap();
+
+    let config = Config::load(&root.join("tinychange.toml")).unwrap();
+    let opts = CommandOpts::builder(root, config)
+        .env_vars([])
+        .build()
+        .unwrap();
+
+    let added = create(&opts, &change("Added", "Added test runner")).unwrap();
+    assert!(added.starts_with(root.join(".tinychange")));
+    create(&opts, &change("Fixed", "Fixed test runner")).unwrap();
+
+    assert!(create(&opts, &change("Unknown", "Message")).is_err());
+    assert!(create(&opts, &change("Added", " ")).is_err());
+
+    let merged = merge(&opts, false).unwrap();
+    assert_eq!(merged.changes.len(), 2);
+    assert!(merged.created_changelog);
+    assert!(!added.exists());
+
+    let changelog = fs_err::read_to_string(root.join("CHANGELOG.md")).unwrap();
+    assert!(changelog.contains("### Added\n\n- Added test runner (by test_runner)"));
+    assert!(changelog.contains("### Fixed\n\n- Fixed test runner (by test_runner)"));
+
+    let merged = merge(&opts, false).unwrap();
+    assert!(merged.changes.is_empty());
+}
+
+#[test]
+fn reports_to_custom_sink() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    let config = crate::config::parse_config(
+        &root.join("tinychange.toml"),
+        include_str!("../tinychange.default.toml"),
+    )
+    .unwrap();
+
+    let messages = Arc::new(Mutex::new(vec![]));
+    let sink = messages.clone();
+    let opts = CommandOpts::builder(root, config)
+        .reporter(move |level: Level, message: &str| {
+            sink.lock().unwrap().push((level, message.to_owned()))
+        })
+        .env_vars([])
+        .build()
+        .unwrap();
+
+    create(&opts, &change("Added", "Added test runner")).unwrap();
+    merge(&opts, false).unwrap();
+
+    let messages = messages.lock().unwrap();
+    assert!(messages.contains(&(Level::Info, "Merging 1 changesets".to_owned())));
+    assert!(messages.iter().any(
+        |(level, message)| *level == Level::Debug && message.starts_with("Reading tinychange")
+    ));
+}
+
+#[test]
+fn flat_grouping_is_chronological() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    let mut config = crate::config::parse_config(
+        &root.join("tinychange.toml"),
+        include_str!("../tinychange.default.toml"),
+    )
+    .unwrap();
+    config.grouping = Grouping::Flat;
+    let opts = CommandOpts::builder(root, config)
+        .env_vars([])
+        .build()
+        .unwrap();
+
+    git(root, &["init", "-q"], 0);
+    // file names sort in the opposite order of the commits
+    change("Fixed", "First")
+        .write_named(&opts, "c.md", false)
+        .unwrap();
+    git(root, &["add", "."], 0);
+    git(root, &["commit", "-q", "-m", "first"], 1_000_000);
+    change("Added", "Second")
+        .write_named(&opts, "b.md", false)
+        .unwrap();
+    git(root, &["add", "."], 0);
+    git(root, &["commit", "-q", "-m", "second"], 2_000_000);
+    change("Changed", "Uncommitted")
+        .write_named(&opts, "a.md", false)
+        .unwrap();
+
+    merge(&opts, false).unwrap();
+
+    let changelog = fs_err::read_to_string(root.join("CHANGELOG.md")).unwrap();
+    assert!(changelog.contains(
+        "- [Fixed] First (by test_runner)\n- [Added] Second (by test_runner)\n- [Changed] Uncommitted (by test_runner)\n"
+    ));
+}
+
+#[test]
+fn builds_config_and_changes_in_code() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    let mut config = Config::new(".changes", "CHANGES.md", ["Added", "Fixed"]);
+    config.directory_scopes = true;
+    let opts = CommandOpts::builder(root, config)
+        .env_vars([])
+        .build()
+        .unwrap();
+
+    let change = TinyChange::new("Added", "Added the parser", "test_runner").with_scope("core");
+    let added = create(&opts, &change).unwrap();
+    assert!(added.starts_with(root.join(".changes/core")));
+
+    merge(&opts, false).unwrap();
+    let changelog = fs_err::read_to_string(root.join("CHANGES.md")).unwrap();
+    assert!(changelog.contains("### Added\n\n#### core\n\n- Added the parser (by test_runner)\n"));
+}
+
+#[test]
+fn keeps_configured_author_as_is() {
+    let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
+    let root = temp_dir.path();
+    git(root, &["init", "-q"], 0);
+    git(root, &["config", "user.name", "janehandle"], 0);
+    git(root, &["config", "user.email", "jane@corp.com"], 0);
+    fs_err::write(root.join(".mailmap"), "Jane Work <jane@corp.com>\n").unwrap();
+
+    let config = Config::new(".tinychange", "CHANGELOG.md", ["Added"]);
+    let opts = |env_vars: Vec<(String, String)>| {
+        CommandOpts::builder(root, config.clone())
+            .env_vars(env_vars)
+            .build()
+            .unwrap()
+    };
+
+    // names from git go through the mailmap, overrides don't
+    assert_eq!(resolve_author(&opts(vec![]), None).unwrap(), "Jane Work");
+    let env_vars = vec![("TINYCHANGE_AUTHOR".to_string(), "janehandle".to_string())];
+    assert_eq!(resolve_author(&opts(env_vars), None).unwrap(), "janehandle");
+}
@@ -0,0 +1,390 @@
+use crate::test::change;
+use crate::{merge_changelog, Attribution, Config, Grouping, TinyChange};
+
+fn config() -> Config {
+    toml::from_str(
+        r#"
+tinylogs_dir = ".tinychange"
+changelog = "CHANGELOG.md"
+categories = ["Added", "Changed", "Fixed"]
+"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn merges_into_unreleased_section() {
+    let changelog = "# Changelog
+
+## [Unreleased]
+
+### Fixed
+
+- Existing fix (by test_runner)
+
+## [1.0.0] - 2025-01-01
+
+### Added
+
+- Initial release (by test_runner)
+";
+    let changes = [change("Added", "New feature"), change("Fixed", "New fix")];
+
+    assert_eq!(
+        merge_changelog(changelog, &changes, &config()).unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+### Added
+
+- New feature (by test_runner)
+
+### Fixed
+
+- Existing fix (by test_runner)
+- New fix (by test_runner)
+
+## [1.0.0] - 2025-01-01
+
+### Added
+
+- Initial release (by test_runner)"
+    );
+}
+
+#[test]
+fn creates_unreleased_section() {
+    let changelog = "# Changelog
+
+## [1.0.0] - 2025-01-01
+";
+
+    assert_eq!(
+        merge_changelog(changelog, &[change("Changed", "Something")], &config()).unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+### Changed
+
+- Something (by test_runner)
+
+## [1.0.0] - 2025-01-01"
+    );
+}
+
+#[test]
+fn fails_on_unknown_content() {
+    let changes = [change("Added", "New feature")];
+    assert!(merge_changelog("", &changes, &config()).is_err());
+    assert!(merge_changelog(
+        "# Changelog\n\n## [Unreleased]\n\n### Added\n- A\n### Removed\n- B\n### Fixed\n- C\n",
+        &changes,
+        &config()
+    )
+    .is_err());
+}
+
+#[test]
+fn hides_matching_authors() {
+    let mut config = config();
+    config.hidden_authors = vec!["dependabot*".to_string(), r"*\[bot\]".to_string()];
+    let changes = [
+        TinyChange {
+            author: "dependabot".to_string(),
+            ..change("Changed", "Bumped serde")
+        },
+        TinyChange {
+            author: "Renovate[bot]".to_string(),
+            ..change("Changed", "Bumped regex\n\nSee the release notes")
+        },
+        change("Fixed", "Fixed a crash"),
+    ];
+    let changelog = "# Changelog\n\n## [Unreleased]\n";
+
+    assert_eq!(
+        merge_changelog(changelog, &changes, &config).unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+### Changed
+
+- Bumped serde
+-   Bumped regex
+  
+  See the release notes
+
+### Fixed
+
+- Fixed a crash (by test_runner)
+"
+    );
+
+    config.hidden_author_credit = Some("automated".to_string());
+    assert!(merge_changelog(changelog, &changes, &config)
+        .unwrap()
+        .contains("- Bumped serde (by automated)\n"));
+
+    config.hidden_authors = vec!["bots/[abc".to_string()];
+    assert!(merge_changelog(changelog, &changes, &config).is_err());
+}
+
+#[test]
+fn lists_contributors() {
+    let mut config = config();
+    config.attribution = Attribution::Contributors;
+    config.flag_new_contributors = true;
+    config.hidden_authors = vec!["dependabot".to_string()];
+    config.authors = toml::from_str(
+        r#"
+jane = { name = "@jane", url = "https://github.com/jane" }
+"#,
+    )
+    .unwrap();
+
+    let changelog = "# Changelog
+
+## [Unreleased]
+
+### Fixed
+
+- Existing fix
+
+### Contributors
+
+- bob
+
+## [1.0.0] - 2025-01-01
+
+### Added
+
+- Initial release (by [@jane](https://github.com/jane))
+";
+    let changes = [
+        TinyChange {
+            author: "jane".to_string(),
+            ..change("Added", "New feature")
+        },
+        TinyChange {
+            author: "dependabot".to_string(),
+            ..change("Changed", "Bumped serde")
+        },
+        change("Fixed", "New fix"),
+        TinyChange {
+            author: "bob".to_string(),
+            ..change("Fixed", "Another fix")
+        },
+    ];
+
+    assert_eq!(
+        merge_changelog(changelog, &changes, &config).unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+### Added
+
+- New feature
+
+### Changed
+
+- Bumped serde
+
+### Fixed
+
+- Existing fix
+- New fix
+- Another fix
+
+### Contributors
+
+- bob
+- [@jane](https://github.com/jane)
+- test_runner (first contribution)
+
+## [1.0.0] - 2025-01-01
+
+### Added
+
+- Initial release (by [@jane](https://github.com/jane))"
+    );
+
+    config.attribution = Attribution::Both;
+    config.contributors_title = Some("Thanks to".to_string());
+    config.flag_new_contributors = false;
+    assert!(merge_changelog("# Changelog\n", &changes[2..], &config)
+        .unwrap()
+        .ends_with(
+            "- New fix (by test_runner)\n- Another fix (by bob)\n\n### Thanks to\n\n- bob\n- test_runner\n"
+        ));
+
+    // the title is matched literally
+    config.contributors_title = Some("Thanks (C++)".to_string());
+    assert!(merge_changelog(
+        "# Changelog\n\n## [Unreleased]\n\n### Thanks (C++)\n\n- bob\n",
+        &changes[2..],
+        &config
+    )
+    .unwrap()
+    .ends_with("### Thanks (C++)\n\n- bob\n- test_runner\n"));
+}
+
+#[test]
+fn groups_by_scope_author_or_nothing() {
+    let mut config = config();
+    let changes = [
+        TinyChange {
+            scope: Some("cli".to_string()),
+            ..change("Added", "New flag")
+        },
+        TinyChange {
+            author: "bob".to_string(),
+            ..change("Fixed", "Unscoped fix")
+        },
+        TinyChange {
+            scope: Some("core".to_string()),
+            ..change("Fixed", "Core fix")
+        },
+    ];
+    let changelog = "# Changelog
+
+## [Unreleased]
+
+Description of the release.
+
+### cli
+
+- [Changed] Existing change (by test_runner)
+
+## [1.0.0] - 2025-01-01
+";
+
+    config.grouping = Grouping::Scope;
+    assert_eq!(
+        merge_changelog(changelog, &changes, &config).unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+Description of the release.
+
+- [Fixed] Unscoped fix (by bob)
+
+### cli
+
+- [Changed] Existing change (by test_runner)
+- [Added] New flag (by test_runner)
+
+### core
+
+- [Fixed] Core fix (by test_runner)
+
+## [1.0.0] - 2025-01-01"
+    );
+
+    config.grouping = Grouping::Author;
+    assert_eq!(
+        merge_changelog("# Changelog\n", &changes, &config).unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+### test_runner
+
+- [Added] **cli**: New flag
+- [Fixed] **core**: Core fix
+
+### bob
+
+- [Fixed] Unscoped fix
+"
+    );
+
+    config.grouping = Grouping::Flat;
+    assert_eq!(
+        merge_changelog(
+            "# Changelog\n\n## [Unreleased]\n\nDescription.\n\n- [Removed] Old entry (by test_runner)\n",
+            &changes,
+            &config
+        )
+        .unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+Description.
+
+- [Removed] Old entry (by test_runner)
+- [Added] **cli**: New flag (by test_runner)
+- [Fixed] Unscoped fix (by bob)
+- [Fixed] **core**: Core fix (by test_runner)
+"
+    );
+    assert!(merge_changelog(
+        "# Changelog\n\n## [Unreleased]\nDescription.\n",
+        &changes,
+        &config
+    )
+    .unwrap()
+    .contains("Description.\n\n- [Added] **cli**: New flag (by test_runner)\n"));
+}
+
+#[test]
+fn matches_categories_literally() {
+    let mut config = config();
+    config.categories = vec!["Added".to_string(), "C++ API".to_string()];
+    let changelog = "# Changelog
+
+## [Unreleased]
+
+### C++ API
+
+- Existing change (by test_runner)
+";
+
+    assert_eq!(
+        merge_changelog(changelog, &[change("C++ API", "New change")], &config).unwrap(),
+        "# Changelog
+
+## [Unreleased]
+
+### C++ API
+
+- Existing change (by test_runner)
+- New change (by test_runner)
+"
+    );
+}
+
+#[test]
+fn fails_on_invalid_section_pattern() {
+    let mut config = config();
+    config.unreleased_pattern = Some("(upcoming".to_string());
+    assert!(merge_changelog(
+        "# Changelog\n\n## [Unreleased]\n",
+        &[change("Added", "New feature")],
+        &config
+    )
+    .is_err());
+}
+
+#[test]
+fn fails_on_category_grouped_section() {
+    let mut config = config();
+    let changelog = "# Changelog
+
+## [Unreleased]
+
+### Added
+
+- Existing change (by test_runner)
+";
+    let changes = [change("Added", "New change")];
+
+    for grouping in [Grouping::Scope, Grouping::Author, Grouping::Flat] {
+        config.grouping = grouping;
+        assert!(merge_changelog(changelog, &changes, &config).is_err());
+    }
+}
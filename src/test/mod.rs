@@ -1,8 +1,17 @@
-use crate::TinyChangeArgs;
-use clap::Parser;
+use crate::{TinyChange, TinyChangeArgs};
+use clap::{CommandFactory, Parser};
 use miette::{Context, IntoDiagnostic};
 use std::path::Path;
 
+mod config;
+mod from_commits;
+mod init;
+mod library;
+mod merge;
+mod naming;
+mod notes;
+mod tinychange;
+
 fn run_changelog_test(test_dir: impl AsRef<Path>) -> miette::Result<String> {
     let test_dir = test_dir.as_ref();
     let temp_dir = temp_dir::TempDir::new().expect("Should create a temporary directory");
@@ -15,7 +24,7 @@ fn run_changelog_test(test_dir: impl AsRef<Path>) -> miette::Result<String> {
         .expect("Should read commands.txt");
 
     for (idx, command) in commands.lines().enumerate() {
-        let args = (|| {
+        let mut args = (|| {
             let words = shell_words::split(command)
                 .into_diagnostic()
                 .context("Failed to split command into shell words")?;
@@ -34,7 +43,10 @@ fn run_changelog_test(test_dir: impl AsRef<Path>) -> miette::Result<String> {
             )
         });
 
-        crate::run(args, "tinychange")
+        // never wait for answers, even when the tests run in a terminal
+        args.non_interactive = true;
+        // keep the personal settings of whoever runs the tests out of the snapshots
+        crate::run_with(args, "tinychange", Default::default(), [])
             .context("Failed to run tinychange")
             .with_context(|| format!("Failed to execute command #{}: {}", idx, command))?;
     }
@@ -47,8 +59,45 @@ fn run_changelog_test(test_dir: impl AsRef<Path>) -> miette::Result<String> {
     Ok(changelog)
 }
 
+/// Runs git in the directory with a fixed identity, committing at the given
+/// Unix time
+fn git(dir: &Path, args: &[&str], time: u64) {
+    let date = format!("@{} +0000", time);
+    let status = std::process::Command::new("git")
+        .args([
+            "-c",
+            "user.name=test_runner",
+            "-c",
+            "user.email=test@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_DATE", &date)
+        .env("GIT_COMMITTER_DATE", &date)
+        .status()
+        .expect("Should run git");
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// Change of the category with the message, by the same author that [git]
+/// commits as
+fn change(kind: &str, message: &str) -> TinyChange {
+    TinyChange::new(kind, message, "test_runner")
+}
+
+#[test]
+fn cli_is_valid() {
+    TinyChangeArgs::command().debug_assert();
+}
+
 #[test]
 fn changelog_tests() {
+    // Render errors at a fixed width, so snapshots don't depend on the terminal
+    miette::set_hook(Box::new(|_| {
+        Box::new(miette::MietteHandlerOpts::new().width(200).build())
+    }))
+    .expect("Should set miette hook");
+
     let cwd = std::env::current_dir().expect("Should get current working directory");
     insta::glob!("cases/*", |path| {
         std::env::set_current_dir(&cwd).expect("Should set current working directory");
@@ -0,0 +1,110 @@
+use crate::naming::{civil_from_days, NameType};
+use crate::test::change;
+use crate::tinychange::TinyChange;
+use std::path::Path;
+
+const SEED: u64 = 0x1a2b3c4d5e6f7a8b;
+
+fn generate(naming: NameType, message: &str, max_length: Option<usize>) -> miette::Result<String> {
+    naming.generate(&change("Added", message), SEED, max_length, Path::new("."))
+}
+
+#[test]
+fn slug_naming() {
+    assert_eq!(
+        generate(
+            NameType::Slug,
+            "Added `from-commits` command!\nWith details",
+            None
+        )
+        .unwrap(),
+        "added-from-commits-command-1a2b3c4"
+    );
+    assert_eq!(
+        generate(
+            NameType::Slug,
+            "One two three four five six seven eight nine",
+            None
+        )
+        .unwrap(),
+        "one-two-three-four-five-six-seven-eight-1a2b3c4"
+    );
+}
+
+#[test]
+fn slug_is_truncated_to_fit() {
+    assert_eq!(
+        generate(NameType::Slug, "Fixed crash on startup", Some(20)).unwrap(),
+        "fixed-crash-1a2b3c4"
+    );
+    assert_eq!(
+        generate(NameType::Slug, "Internationalization", Some(12)).unwrap(),
+        "inte-1a2b3c4"
+    );
+    assert_eq!(
+        generate(NameType::Slug, "Fixed crash", Some(7)).unwrap(),
+        "1a2b3c4"
+    );
+}
+
+#[test]
+fn template_naming() {
+    let naming = NameType::Template("{kind}_{author}-{slug}-{hash}".to_string());
+    let change = TinyChange {
+        author: "Test Runner".to_string(),
+        ..change("Added", "Fixed crash")
+    };
+    assert_eq!(
+        naming
+            .generate(&change, SEED, None, Path::new("."))
+            .unwrap(),
+        "added_test-runner-fixed-crash-1a2b3c4"
+    );
+
+    let naming = NameType::Template("{slug}".to_string());
+    assert_eq!(generate(naming, "", None).ok(), None);
+
+    let naming = NameType::Template("{kind}-{slug}".to_string());
+    assert!(generate(naming, "Fixed crash", None).is_err());
+
+    let naming = NameType::Template("{kind}-{unknown}".to_string());
+    assert!(generate(naming, "Fixed crash", None).is_err());
+
+    let naming = NameType::Template("{kind}-{hash}".to_string());
+    assert!(generate(naming, "Fixed crash", Some(5)).is_err());
+}
+
+#[test]
+fn hash_naming() {
+    assert_eq!(
+        generate(NameType::Hash, "Fixed crash", None).unwrap(),
+        "1a2b3c4d5e6f7a8b"
+    );
+    assert_eq!(
+        generate(NameType::Hash, "Fixed crash", Some(10)).unwrap(),
+        "1a2b3c4d5e"
+    );
+    assert!(generate(NameType::Hash, "Fixed crash", Some(5)).is_err());
+}
+
+#[test]
+fn naming_deserialization() {
+    #[derive(serde::Deserialize)]
+    struct Config {
+        naming: NameType,
+    }
+
+    let parse = |s: &str| toml::from_str::<Config>(s).unwrap().naming;
+    assert_eq!(parse(r#"naming = "date""#), NameType::Date);
+    assert_eq!(
+        parse(r#"naming = { template = "{date}-{slug}" }"#),
+        NameType::Template("{date}-{slug}".to_string())
+    );
+}
+
+#[test]
+fn dates() {
+    assert_eq!(civil_from_days(0), (1970, 1, 1));
+    assert_eq!(civil_from_days(11016), (2000, 2, 29));
+    assert_eq!(civil_from_days(20024), (2024, 10, 28));
+}
@@ -0,0 +1,65 @@
+use crate::changelog::{regex_for_section, regex_for_version, section_body};
+
+const CHANGELOG: &str = "# Changelog
+
+## [Unreleased]
+
+### Fixed
+
+- Unreleased fix (by juh9870)
+
+## [1.10.0] - 2025-02-01
+
+### Added
+
+- Notes command (by juh9870)
+
+### Fixed
+
+- A bug (by juh9870)
+
+## v1.1.0
+
+### Changed
+
+- Something (by juh9870)
+
+## [1.0.0] - 2025-01-01
+";
+
+#[test]
+fn finds_version_notes() {
+    assert_eq!(
+        section_body(CHANGELOG, &regex_for_version("1.10.0")).as_deref(),
+        Some("### Added\n\n- Notes command (by juh9870)\n\n### Fixed\n\n- A bug (by juh9870)")
+    );
+    assert_eq!(
+        section_body(CHANGELOG, &regex_for_version("v1.10.0")),
+        section_body(CHANGELOG, &regex_for_version("1.10.0"))
+    );
+    assert_eq!(
+        section_body(CHANGELOG, &regex_for_version("1.1.0")).as_deref(),
+        Some("### Changed\n\n- Something (by juh9870)")
+    );
+    assert_eq!(
+        section_body(CHANGELOG, &regex_for_version("1.0.0")).as_deref(),
+        Some("")
+    );
+}
+
+#[test]
+fn does_not_match_version_prefixes() {
+    assert_eq!(section_body(CHANGELOG, &regex_for_version("1.1")), None);
+    assert_eq!(
+        section_body(CHANGELOG, &regex_for_version("1.10.0.1")),
+        None
+    );
+}
+
+#[test]
+fn finds_unreleased_notes() {
+    assert_eq!(
+        section_body(CHANGELOG, &regex_for_section("unreleased")).as_deref(),
+        Some("### Fixed\n\n- Unreleased fix (by juh9870)")
+    );
+}
@@ -0,0 +1,34 @@
+use crate::test::change;
+use crate::tinychange::TinyChange;
+
+#[test]
+fn content_hash_is_stable() {
+    // Filenames of existing tinychanges are derived from this hash, so it
+    // must not change between versions
+    assert_eq!(
+        change("Added", "Added test runner").content_hash(0),
+        0x84c942fd123adaf3
+    );
+}
+
+#[test]
+fn content_hash_depends_on_salt_and_fields() {
+    let change = change("Added", "Added test runner");
+    assert_ne!(change.content_hash(0), change.content_hash(1));
+
+    let mut other = change.clone();
+    other.author = "other_runner".to_string();
+    assert_ne!(change.content_hash(0), other.content_hash(0));
+}
+
+#[test]
+fn renders_markdown_without_config() {
+    let change = TinyChange {
+        scope: Some("cli".to_string()),
+        ..change("Added", "Added test runner")
+    };
+    assert_eq!(
+        change.as_markdown().to_string(),
+        "- Added test runner (by test_runner)"
+    );
+}
@@ -1,34 +1,346 @@
-use crate::config::CommandOpts;
-use miette::{bail, miette};
+use crate::config::{Attribution, CommandOpts, Config, Grouping};
+use crate::ignore::IgnoreRules;
+use globset::GlobSet;
+use miette::{bail, miette, Context, IntoDiagnostic};
+use std::borrow::Cow;
 use std::fmt::Display;
-use std::hash::{Hash, Hasher};
-use std::{fmt, hash};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fmt, io};
 
+/// Lists the tinychange files in the tinychanges directory, sorted by name
+///
+/// Files with another extension and the ones matching the ignore rules are
+/// skipped
+pub fn tinychange_files(opts: &CommandOpts) -> miette::Result<Vec<PathBuf>> {
+    let ignore = IgnoreRules::load(opts)?;
+
+    let mut files = vec![];
+    let mut dirs = vec![opts.tinychanges_dir().to_owned()];
+    while let Some(dir) = dirs.pop() {
+        for file in fs_err::read_dir(&dir).into_diagnostic()? {
+            let path = file.into_diagnostic()?.path();
+            let relative = path.strip_prefix(opts.tinychanges_dir()).unwrap_or(&path);
+            let hidden = relative
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden || ignore.is_ignored(relative) {
+                continue;
+            }
+
+            if path.is_dir() {
+                dirs.push(path);
+            } else if has_extension(&path, opts.extension()) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether the file name ends with the extension, which may have several
+/// parts like `tar.gz`
+fn has_extension(path: &Path, extension: &str) -> bool {
+    path.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy();
+        name.strip_suffix(extension)
+            .and_then(|stem| stem.strip_suffix('.'))
+            .is_some_and(|stem| !stem.is_empty())
+    })
+}
+
+/// A single pending change, stored in its own file until it's merged into
+/// the changelog
+///
+/// Use [TinyChange::new] to create one
 #[derive(Debug, Clone, Hash)]
+#[non_exhaustive]
 pub struct TinyChange {
+    /// Category of the change, one of the configured categories
     pub kind: String,
+    /// Markdown description of the change
     pub message: String,
+    /// Name of the person credited for the change
     pub author: String,
+    /// Package or topic of the change, which is the subdirectory of the
+    /// tinychanges directory the file is stored in
+    pub scope: Option<String>,
 }
 
+/// How many salts to try before giving up on finding a free filename
+const MAX_WRITE_ATTEMPTS: u64 = 16;
+
 impl TinyChange {
-    pub fn filename(&self, opts: &CommandOpts) -> miette::Result<String> {
-        let hash = {
-            let mut hasher = hash::DefaultHasher::new();
-            self.hash(&mut hasher);
-            hasher.finish()
-        };
+    /// Creates a change without a scope
+    pub fn new(
+        kind: impl Into<String>,
+        message: impl Into<String>,
+        author: impl Into<String>,
+    ) -> Self {
+        Self {
+            kind: kind.into(),
+            message: message.into(),
+            author: author.into(),
+            scope: None,
+        }
+    }
+
+    /// Sets the scope of the change, such as `core/parser`
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Hashes the contents of the change together with the salt
+    ///
+    /// Uses 64-bit FNV-1a, which (unlike the std `DefaultHasher`) is
+    /// guaranteed to produce the same hash on every platform and Rust version
+    pub(crate) fn content_hash(&self, salt: u64) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+        const PRIME: u64 = 0x100000001b3;
+
+        [
+            self.kind.as_bytes(),
+            &[0xff],
+            self.author.as_bytes(),
+            &[0xff],
+            self.message.as_bytes(),
+            &[0xff],
+            &salt.to_le_bytes(),
+        ]
+        .iter()
+        .flat_map(|bytes| bytes.iter())
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(PRIME)
+        })
+    }
 
+    /// Generates the filename of the tinychange, including the extension
+    pub(crate) fn filename(&self, opts: &CommandOpts, salt: u64) -> miette::Result<String> {
         Ok(format!(
-            "{}.md",
-            opts.naming().generate(hash, opts.max_filename_length())?
+            "{}.{}",
+            opts.naming().generate(
+                self,
+                self.content_hash(salt),
+                opts.max_filename_length(),
+                opts.workdir()
+            )?,
+            opts.extension()
         ))
     }
 
-    pub fn as_markdown(&self) -> MarkdownChange {
-        MarkdownChange(self)
+    /// Writes the tinychange into a new file in the tinychanges directory,
+    /// retrying with another salt if the generated filename is already taken
+    ///
+    /// The salt is derived from the configured naming seed if there is one,
+    /// or from the current time otherwise
+    ///
+    /// Returns the path to the new file
+    pub fn write(&self, opts: &CommandOpts) -> miette::Result<PathBuf> {
+        self.write_prefixed(opts, "")
+    }
+
+    /// Same as [`TinyChange::write`], but with the given prefix before the
+    /// generated filename, so that a batch of tinychanges is listed in the
+    /// order it was written in
+    pub(crate) fn write_prefixed(
+        &self,
+        opts: &CommandOpts,
+        prefix: &str,
+    ) -> miette::Result<PathBuf> {
+        let salt = opts.naming_seed().unwrap_or_else(|| {
+            let time = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos() as u64;
+            time ^ ((std::process::id() as u64) << 32)
+        });
+
+        for attempt in 0..MAX_WRITE_ATTEMPTS {
+            let filename = self.filename(opts, salt.wrapping_add(attempt))?;
+            if let Some(path) = self.write_named(opts, &format!("{prefix}{filename}"), false)? {
+                return Ok(path);
+            }
+        }
+
+        bail!(
+            "Failed to find a free filename for the tinychange within {} attempts",
+            MAX_WRITE_ATTEMPTS
+        )
+    }
+
+    /// Writes the tinychange into a new file in the tinychanges directory,
+    /// with the filename generated from the given salt
+    ///
+    /// Returns `None` without writing anything if the file already exists
+    pub(crate) fn write_salted(
+        &self,
+        opts: &CommandOpts,
+        salt: u64,
+    ) -> miette::Result<Option<PathBuf>> {
+        self.write_named(opts, &self.filename(opts, salt)?, false)
     }
 
+    /// Writes the tinychange into the tinychanges directory under the given filename
+    ///
+    /// Returns `None` without writing anything if the file already exists,
+    /// unless `overwrite` is set
+    pub(crate) fn write_named(
+        &self,
+        opts: &CommandOpts,
+        filename: &str,
+        overwrite: bool,
+    ) -> miette::Result<Option<PathBuf>> {
+        let dir = self.dir(opts)?;
+        let path = dir.join(filename);
+
+        fs_err::create_dir_all(&dir)
+            .into_diagnostic()
+            .context("Failed to create tinychange directory")?;
+
+        let mut file = match fs_err::OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .create(overwrite)
+            .create_new(!overwrite)
+            .open(&path)
+        {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => return Ok(None),
+            Err(err) => {
+                return Err(err)
+                    .into_diagnostic()
+                    .context("Failed to create tinychange file")
+            }
+        };
+
+        file.write_all(self.serialize().as_bytes())
+            .into_diagnostic()
+            .context("Failed to write tinychange file")?;
+
+        Ok(Some(path))
+    }
+
+    /// Checks that the kind is one of the configured categories, and that
+    /// the message and the author are not empty
+    pub fn validate(&self, opts: &CommandOpts) -> miette::Result<()> {
+        if !opts.categories().contains(&self.kind) {
+            bail!("Unknown change type: {}", self.kind)
+        }
+        if self.message.trim().is_empty() {
+            bail!("Empty message")
+        }
+        if self.author.trim().is_empty() {
+            bail!("Empty author")
+        }
+        Ok(())
+    }
+
+    /// Returns the directory for the tinychange file, which is the
+    /// subdirectory of the tinychanges directory matching the scope
+    fn dir(&self, opts: &CommandOpts) -> miette::Result<PathBuf> {
+        let Some(scope) = &self.scope else {
+            return Ok(opts.tinychanges_dir().to_owned());
+        };
+
+        let mut dir = opts.tinychanges_dir().to_owned();
+        for part in scope.split('/') {
+            if part.is_empty()
+                || part.starts_with('.')
+                || sanitise_file_name::sanitise(part) != part
+            {
+                bail!(
+                    help = "Scopes are directory names, optionally nested with `/`",
+                    "Invalid scope: {}",
+                    scope
+                );
+            }
+            dir.push(part);
+        }
+        Ok(dir)
+    }
+
+    /// Reads the tinychange file, taking the scope from its subdirectory if
+    /// `directory_scopes` are enabled
+    pub fn read(opts: &CommandOpts, path: &Path) -> miette::Result<Self> {
+        let content = fs_err::read_to_string(path).into_diagnostic()?;
+        let mut change = Self::deserialize(opts, content)
+            .with_context(|| format!("Failed to deserialize tinychange at {}", path.display()))?;
+
+        if opts.directory_scopes() {
+            change.scope = path
+                .parent()
+                .and_then(|dir| dir.strip_prefix(opts.tinychanges_dir()).ok())
+                .map(|dir| {
+                    dir.components()
+                        .map(|part| part.as_os_str().to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                })
+                .filter(|scope| !scope.is_empty());
+        }
+
+        Ok(change)
+    }
+
+    /// Renders the changelog entry as in a changelog grouped by category,
+    /// crediting the author under their own name
+    ///
+    /// Use [TinyChange::as_markdown_with] to follow the configuration
+    pub fn as_markdown(&self) -> MarkdownChange<'_> {
+        MarkdownChange {
+            change: self,
+            author: Some(Cow::Borrowed(&self.author)),
+            show_kind: false,
+            show_scope: false,
+        }
+    }
+
+    /// Renders the changelog entry, crediting the author as configured in
+    /// the `authors` table
+    ///
+    /// The parts shown by the headers of the configured grouping, such as the
+    /// category, are left out of the entry
+    ///
+    /// Fails if one of the `hidden_authors` patterns is invalid
+    pub fn as_markdown_with<'a>(
+        &'a self,
+        config: &'a Config,
+    ) -> miette::Result<MarkdownChange<'a>> {
+        Ok(self.as_markdown_hiding(config, &config.hidden_author_globs()?))
+    }
+
+    /// Same as [TinyChange::as_markdown_with], with the `hidden_authors`
+    /// patterns compiled by the caller
+    pub(crate) fn as_markdown_hiding<'a>(
+        &'a self,
+        config: &'a Config,
+        hidden_authors: &GlobSet,
+    ) -> MarkdownChange<'a> {
+        let (show_kind, show_scope, show_author) = match config.grouping {
+            Grouping::Category => (false, false, true),
+            Grouping::Scope => (true, false, true),
+            Grouping::Author => (true, true, false),
+            Grouping::Flat => (true, true, true),
+        };
+        let author = match config.attribution {
+            Attribution::Entry | Attribution::Both if show_author => {
+                config.credit(&self.author, hidden_authors)
+            }
+            _ => None,
+        };
+
+        MarkdownChange {
+            change: self,
+            author,
+            show_kind,
+            show_scope,
+        }
+    }
+
+    /// Renders the tinychange in the format of the tinychange files
     pub fn serialize(&self) -> String {
         format!(
             "- Author: {}\n- Kind: {}\n---\n{}",
@@ -36,7 +348,8 @@ impl TinyChange {
         )
     }
 
-    pub fn deserialize(opts: &CommandOpts, content: String) -> miette::Result<Self> {
+    /// Parses the content of a tinychange file
+    pub(crate) fn deserialize(opts: &CommandOpts, content: String) -> miette::Result<Self> {
         let mut lines = content.lines().peekable();
 
         let (field, author) = lines
@@ -71,9 +384,7 @@ impl TinyChange {
         if lines.next() != Some("---") {
             bail!("Invalid format: missing message separator")
         }
-        let message: String =
-            normalize_line_endings::normalized(lines.collect::<Vec<_>>().join("\n").trim().chars())
-                .collect();
+        let message = normalize_message(&lines.collect::<Vec<_>>().join("\n"));
 
         if author.is_empty() {
             bail!("Empty author field")
@@ -95,25 +406,53 @@ impl TinyChange {
             kind,
             message,
             author,
+            scope: None,
         })
     }
 }
 
-pub struct MarkdownChange<'a>(&'a TinyChange);
+/// Trims the message and normalizes its line endings, the same way
+/// messages are read back from tinychange files
+pub fn normalize_message(message: &str) -> String {
+    normalize_line_endings::normalized(message.trim().chars()).collect()
+}
+
+/// Changelog entry of a tinychange, see [TinyChange::as_markdown_with]
+pub struct MarkdownChange<'a> {
+    change: &'a TinyChange,
+    /// Credit for the author, `None` if the attribution is hidden
+    author: Option<Cow<'a, str>>,
+    /// Whether the category is shown in front of the message
+    show_kind: bool,
+    /// Whether the scope is shown in front of the message
+    show_scope: bool,
+}
 
 impl Display for MarkdownChange<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        if !self.0.message.contains("\n") {
-            write!(f, "- {} (by {})", self.0.message, self.0.author)?;
+        let mut message = Cow::Borrowed(&self.change.message);
+        if let Some(scope) = self.change.scope.as_ref().filter(|_| self.show_scope) {
+            message = Cow::Owned(format!("**{}**: {}", scope, message));
+        }
+        if self.show_kind {
+            message = Cow::Owned(format!("[{}] {}", self.change.kind, message));
+        }
+
+        if !message.contains("\n") {
+            write!(f, "- {}", message)?;
+            if let Some(author) = &self.author {
+                write!(f, " (by {})", author)?;
+            }
         } else {
-            let msg = self
-                .0
-                .message
+            let msg = message
                 .lines()
                 .map(|line| format!("  {}", line))
                 .collect::<Vec<_>>()
                 .join("\n");
-            write!(f, "- {}\n  By: {}", msg, self.0.author)?;
+            write!(f, "- {}", msg)?;
+            if let Some(author) = &self.author {
+                write!(f, "\n  By: {}", author)?;
+            }
         }
         Ok(())
     }